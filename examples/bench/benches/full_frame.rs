@@ -0,0 +1,161 @@
+//! Compare the bufferless and the `buffered` drawing paths on a 128x128 display.
+//!
+//! The mode is picked at compile time, so run the benchmarks once for each:
+//!
+//! ```text
+//! cargo bench
+//! cargo bench --features buffered
+//! ```
+//!
+//! Criterion measures the time spent in the driver on the host, which only hints at the cost on a
+//! microcontroller. The transfer counts printed for every scenario do carry over as they are. They
+//! are taken on the first frame, repeating a frame in the same window saves the four transfers of
+//! the column and row commands:
+//!
+//! | scenario         | mode       | transfers | bytes  |
+//! |------------------|------------|-----------|--------|
+//! | full redraw      | bufferless | 133       | 32775  |
+//! | full redraw      | buffered   | 6         | 32775  |
+//! | 16 pixel update  | bufferless | 96        | 144    |
+//! | 16 pixel update  | buffered   | 6         | 32775  |
+//! | 16 pixel row     | bufferless | 6         | 39     |
+//! | 16 pixel row     | buffered   | 6         | 32775  |
+//! | 16 pixel run     | bufferless | 21        | 39     |
+//! | 64x64 fill       | bufferless | 37        | 8199   |
+//! | 64x64 fill       | buffered   | 6         | 32775  |
+//! | clipped fill     | bufferless | 13        | 2055   |
+//! | clipped fill     | buffered   | 6         | 32775  |
+//! | flipped redraw   | buffered   | 133       | 32775  |
+//! | tinted redraw    | buffered   | 133       | 32775  |
+//!
+//! A full frame is the same amount of data either way, about 33 ms on an 8 MHz SPI bus. Without a
+//! buffer, fills are sent in 256 byte chunks while single pixels each pay for their own window,
+//! unless they line up into a row like the glyphs of a font. The buffer costs 32 KiB of RAM and
+//! turns even a handful of changed pixels into a full frame, so sparse updates are far cheaper
+//! without it. The fill scenarios compare a rectangle that is fully
+//! on screen, which skips the per-pixel clipping checks, with one hanging off the corner. Flipping
+//! horizontally or tinting sends the frame row by row and copies every pixel on the way.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+use ssd1351::builder::Builder;
+use ssd1351::mode::GraphicsMode;
+use ssd1351_bench::CountingInterface;
+
+#[cfg(not(feature = "buffered"))]
+const MODE: &str = "bufferless";
+#[cfg(feature = "buffered")]
+const MODE: &str = "buffered";
+
+type Display = GraphicsMode<'static, CountingInterface>;
+
+#[cfg(not(feature = "buffered"))]
+fn display(iface: CountingInterface) -> Display {
+    Builder::new().connect_interface(iface).into()
+}
+
+#[cfg(feature = "buffered")]
+fn display(iface: CountingInterface) -> Display {
+    use ssd1351::framebuffer::BYTES_PER_PIXEL;
+
+    let buffer = vec![0; 128 * 128 * BYTES_PER_PIXEL].leak();
+    Builder::new().connect_interface(iface, buffer).into()
+}
+
+#[cfg(not(feature = "buffered"))]
+fn show(_display: &mut Display) {}
+
+#[cfg(feature = "buffered")]
+fn show(display: &mut Display) {
+    display.flush().unwrap();
+}
+
+fn full_redraw(display: &mut Display) {
+    let area = display.bounding_box();
+    display.fill_solid(&area, Rgb565::BLUE).unwrap();
+    show(display);
+}
+
+fn sparse_update(display: &mut Display) {
+    let pixels = (0..16).map(|i| Pixel(Point::new(i * 8, i * 8), Rgb565::RED));
+    display.draw_iter(pixels).unwrap();
+    show(display);
+}
+
+fn row_update(display: &mut Display) {
+    let pixels = (0..16).map(|i| Pixel(Point::new(i, 8), Rgb565::RED));
+    display.draw_iter(pixels).unwrap();
+    show(display);
+}
+
+#[cfg(not(feature = "buffered"))]
+fn pixel_run(display: &mut Display) {
+    display.begin_pixels();
+    for x in 0..16 {
+        display.set_pixel(x, 8, 0xF800).unwrap();
+    }
+    display.end_pixels();
+}
+
+fn fill(display: &mut Display, top_left: Point) {
+    let area = Rectangle::new(top_left, Size::new(64, 64));
+    display.fill_solid(&area, Rgb565::GREEN).unwrap();
+    show(display);
+}
+
+fn visible_fill(display: &mut Display) {
+    fill(display, Point::new(32, 32));
+}
+
+fn clipped_fill(display: &mut Display) {
+    fill(display, Point::new(96, 96));
+}
+
+#[cfg(feature = "buffered")]
+fn flipped_redraw(display: &mut Display) {
+    display.set_flip_horizontal(true);
+    full_redraw(display);
+    display.set_flip_horizontal(false);
+}
+
+#[cfg(feature = "buffered")]
+fn tinted_redraw(display: &mut Display) {
+    display.set_tint(255, 200, 128);
+    full_redraw(display);
+    display.set_tint(255, 255, 255);
+}
+
+fn scenario(c: &mut Criterion, name: &str, draw: fn(&mut Display)) {
+    let iface = CountingInterface::default();
+    let mut display = display(iface.clone());
+
+    draw(&mut display);
+    let stats = iface.take();
+    println!(
+        "{name} ({MODE}): {} transfers, {} bytes",
+        stats.transfers, stats.bytes
+    );
+
+    c.bench_function(&format!("{name} ({MODE})"), |b| {
+        b.iter(|| draw(black_box(&mut display)))
+    });
+}
+
+fn benches(c: &mut Criterion) {
+    scenario(c, "full redraw", full_redraw);
+    scenario(c, "16 pixel update", sparse_update);
+    scenario(c, "16 pixel row", row_update);
+    #[cfg(not(feature = "buffered"))]
+    scenario(c, "16 pixel run", pixel_run);
+    scenario(c, "64x64 fill", visible_fill);
+    scenario(c, "clipped fill", clipped_fill);
+    #[cfg(feature = "buffered")]
+    scenario(c, "flipped redraw", flipped_redraw);
+    #[cfg(feature = "buffered")]
+    scenario(c, "tinted redraw", tinted_redraw);
+}
+
+criterion_group!(full_frame, benches);
+criterion_main!(full_frame);
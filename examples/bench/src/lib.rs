@@ -0,0 +1,57 @@
+//! Simulated display interface for benchmarking the SSD1351 driver on the host
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+
+/// Transfers and bytes sent through a [`CountingInterface`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Number of `send_commands` and `send_data` calls
+    pub transfers: usize,
+    /// Number of bytes that would have gone over the bus
+    pub bytes: usize,
+}
+
+/// Interface that drops everything it is handed and only counts what would go over the bus.
+/// Clones share their counters, so one clone can be kept to read the stats after the other has
+/// been moved into the display.
+#[derive(Clone, Default)]
+pub struct CountingInterface {
+    stats: Rc<Cell<Stats>>,
+}
+
+impl CountingInterface {
+    /// Return the stats collected so far and reset them
+    pub fn take(&self) -> Stats {
+        self.stats.take()
+    }
+
+    fn record(&self, format: DataFormat<'_>) -> Result<(), DisplayError> {
+        let bytes = match format {
+            DataFormat::U8(data) => data.len(),
+            DataFormat::U16(data) => data.len() * 2,
+            DataFormat::U16BE(data) | DataFormat::U16LE(data) => data.len() * 2,
+            DataFormat::U8Iter(iter) => iter.count(),
+            DataFormat::U16BEIter(iter) | DataFormat::U16LEIter(iter) => iter.count() * 2,
+            _ => return Err(DisplayError::DataFormatNotImplemented),
+        };
+        let stats = self.stats.get();
+        self.stats.set(Stats {
+            transfers: stats.transfers + 1,
+            bytes: stats.bytes + bytes,
+        });
+        Ok(())
+    }
+}
+
+impl WriteOnlyDataCommand for CountingInterface {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.record(cmd)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.record(buf)
+    }
+}
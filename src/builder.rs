@@ -13,6 +13,7 @@ use display_interface::WriteOnlyDataCommand;
 pub struct Builder {
     display_size: DisplaySize,
     rotation: DisplayRotation,
+    reset_active_low: bool,
 }
 
 impl Default for Builder {
@@ -22,11 +23,13 @@ impl Default for Builder {
 }
 
 impl Builder {
-    /// Create new builder with a default size of 128 x 128 pixels and no rotation.
+    /// Create new builder with a default size of 128 x 128 pixels, no rotation and an active low
+    /// reset line.
     pub fn new() -> Self {
         Self {
             display_size: DisplaySize::Display128x128,
             rotation: DisplayRotation::Rotate0,
+            reset_active_low: true,
         }
     }
 
@@ -45,28 +48,88 @@ impl Builder {
         Self { rotation, ..*self }
     }
 
+    /// Set the polarity of the reset line used by `reset`. The SSD1351 `RES#` pin is active low,
+    /// which is the default, but boards with an inverting buffer on the line need an active high
+    /// pulse instead.
+    pub fn with_reset_active_low(&self, reset_active_low: bool) -> Self {
+        Self {
+            reset_active_low,
+            ..*self
+        }
+    }
+
     #[cfg(feature = "buffered")]
     /// Finish the builder and use the given interface to communicate with the display
-    pub fn connect_interface<DI>(
+    ///
+    /// # Panics
+    ///
+    /// Turning the result into a [`GraphicsMode`](crate::mode::GraphicsMode) panics if `buffer`
+    /// does not hold `BYTES_PER_PIXEL` bytes for every pixel of the display size.
+    pub fn connect_interface<'a, DI>(
         &self,
         display_interface: DI,
-        buffer: &'static mut [u8],
-    ) -> DisplayMode<RawMode<DI>>
+        buffer: &'a mut [u8],
+    ) -> DisplayMode<RawMode<'a, DI>>
     where
         DI: WriteOnlyDataCommand,
     {
-        assert_eq!(buffer.len(), self.display_size.num_pixels() * 2);
-        let properties = Display::new(display_interface, self.display_size, self.rotation);
-        DisplayMode::<RawMode<DI>>::new(properties, buffer)
+        let mut properties = Display::new(display_interface, self.display_size, self.rotation);
+        properties.set_reset_active_low(self.reset_active_low);
+        DisplayMode::<RawMode<'a, DI>>::new(properties, buffer)
     }
 
     #[cfg(not(feature = "buffered"))]
     /// Finish the builder and use the given interface to communicate with the display
-    pub fn connect_interface<DI>(&self, display_interface: DI) -> DisplayMode<RawMode<DI>>
+    pub fn connect_interface<'a, DI>(&self, display_interface: DI) -> DisplayMode<RawMode<'a, DI>>
     where
         DI: WriteOnlyDataCommand,
     {
-        let properties = Display::new(display_interface, self.display_size, self.rotation);
-        DisplayMode::<RawMode<DI>>::new(properties)
+        let mut properties = Display::new(display_interface, self.display_size, self.rotation);
+        properties.set_reset_active_low(self.reset_active_low);
+        DisplayMode::<RawMode<'a, DI>>::new(properties)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "buffered")]
+    use crate::framebuffer::BYTES_PER_PIXEL;
+    use crate::mock::{MockDelay, MockInterface, MockPin};
+    use crate::mode::GraphicsMode;
+    use hal::digital::PinState::{High, Low};
+
+    fn reset_levels(builder: Builder) -> Vec<hal::digital::PinState> {
+        #[cfg(feature = "buffered")]
+        let mut buffer = vec![0; 128 * 128 * BYTES_PER_PIXEL];
+        #[cfg(feature = "buffered")]
+        let mut display: GraphicsMode<_> = builder
+            .connect_interface(MockInterface::default(), &mut buffer)
+            .into();
+        #[cfg(not(feature = "buffered"))]
+        let mut display: GraphicsMode<_> =
+            builder.connect_interface(MockInterface::default()).into();
+
+        let mut rst = MockPin::default();
+        display.reset(&mut rst, &mut MockDelay::default()).unwrap();
+        rst.levels
+    }
+
+    #[test]
+    fn reset_pulses_the_configured_level() {
+        assert_eq!(reset_levels(Builder::new()), [High, Low, High]);
+        let builder = Builder::new().with_reset_active_low(false);
+        assert_eq!(reset_levels(builder), [Low, High, Low]);
+    }
+
+    #[cfg(feature = "buffered")]
+    #[test]
+    #[should_panic(expected = "framebuffer does not match the display size")]
+    fn a_buffer_for_the_wrong_size_is_rejected() {
+        let mut buffer = vec![0; 128 * 128 * BYTES_PER_PIXEL];
+        let _: GraphicsMode<_> = Builder::new()
+            .with_size(DisplaySize::Display128x96)
+            .connect_interface(MockInterface::default(), &mut buffer)
+            .into();
     }
 }
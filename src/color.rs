@@ -0,0 +1,153 @@
+//! Drawing from higher bit depth colours
+//!
+//! The display works in RGB565. [`ColorConverted`] wraps a [`GraphicsMode`] as a `DrawTarget` for
+//! `Rgb888` or `Rgb666` sources, rounding every pixel to the nearest RGB565 colour as it is drawn.
+//!
+//! ```rust,ignore
+//! let mut target = display.color_converted::<Rgb888>();
+//! image.draw(&mut target).unwrap();
+//! ```
+
+use core::marker::PhantomData;
+
+use crate::error::Error;
+use crate::mode::GraphicsMode;
+use display_interface::WriteOnlyDataCommand;
+use embedded_graphics_core::pixelcolor::{Rgb565, Rgb666, Rgb888, RgbColor};
+use embedded_graphics_core::prelude::{DrawTarget, OriginDimensions, Pixel, PixelColor, Size};
+use embedded_graphics_core::primitives::Rectangle;
+
+/// Scale a colour channel from `from_max` to `to_max`, rounding to the nearest value
+fn scale_channel(value: u8, from_max: u8, to_max: u8) -> u8 {
+    ((value as u16 * to_max as u16 + from_max as u16 / 2) / from_max as u16) as u8
+}
+
+/// Colours that can be drawn to the display after conversion
+pub trait IntoRgb565: PixelColor {
+    /// Convert into the nearest RGB565 colour
+    fn into_rgb565(self) -> Rgb565;
+}
+
+impl IntoRgb565 for Rgb565 {
+    fn into_rgb565(self) -> Rgb565 {
+        self
+    }
+}
+
+impl IntoRgb565 for Rgb666 {
+    fn into_rgb565(self) -> Rgb565 {
+        Rgb565::new(
+            scale_channel(self.r(), Rgb666::MAX_R, Rgb565::MAX_R),
+            self.g(),
+            scale_channel(self.b(), Rgb666::MAX_B, Rgb565::MAX_B),
+        )
+    }
+}
+
+impl IntoRgb565 for Rgb888 {
+    fn into_rgb565(self) -> Rgb565 {
+        Rgb565::new(
+            scale_channel(self.r(), Rgb888::MAX_R, Rgb565::MAX_R),
+            scale_channel(self.g(), Rgb888::MAX_G, Rgb565::MAX_G),
+            scale_channel(self.b(), Rgb888::MAX_B, Rgb565::MAX_B),
+        )
+    }
+}
+
+/// `DrawTarget` drawing colours of type `C` to a [`GraphicsMode`], see the
+/// [module documentation](self)
+pub struct ColorConverted<'a, 'b, DI, C>
+where
+    DI: WriteOnlyDataCommand,
+{
+    display: &'a mut GraphicsMode<'b, DI>,
+    color: PhantomData<C>,
+}
+
+impl<'b, DI: WriteOnlyDataCommand> GraphicsMode<'b, DI> {
+    /// Draw colours of type `C` to the display, converting them to RGB565 on the fly
+    pub fn color_converted<C: IntoRgb565>(&mut self) -> ColorConverted<'_, 'b, DI, C> {
+        ColorConverted {
+            display: self,
+            color: PhantomData,
+        }
+    }
+}
+
+#[maybe_async::maybe_async(AFIT)]
+impl<DI: WriteOnlyDataCommand, C: IntoRgb565> DrawTarget for ColorConverted<'_, '_, DI, C> {
+    type Color = C;
+    type Error = Error;
+
+    async fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        self.display
+            .draw_iter(
+                pixels
+                    .into_iter()
+                    .map(|Pixel(pos, color)| Pixel(pos, color.into_rgb565())),
+            )
+            .await
+    }
+
+    async fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        self.display
+            .fill_contiguous(area, colors.into_iter().map(IntoRgb565::into_rgb565))
+            .await
+    }
+
+    async fn fill_solid(
+        &mut self,
+        area: &Rectangle,
+        color: Self::Color,
+    ) -> Result<(), Self::Error> {
+        self.display.fill_solid(area, color.into_rgb565()).await
+    }
+}
+
+impl<DI: WriteOnlyDataCommand, C> OriginDimensions for ColorConverted<'_, '_, DI, C> {
+    fn size(&self) -> Size {
+        self.display.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+    use crate::properties::{DisplayRotation, DisplaySize};
+    use embedded_graphics_core::prelude::Point;
+
+    /// Draw `color` at 3, 4 through a `ColorConverted` and check that it arrives as `expected`
+    fn assert_converted<C: IntoRgb565>(color: C, expected: u16) {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let mut target = display.color_converted::<C>();
+        target.draw_iter([Pixel(Point::new(3, 4), color)]).unwrap();
+
+        #[cfg(not(feature = "buffered"))]
+        assert_eq!(mock::ram_data(&mock.take()), expected.to_be_bytes());
+        #[cfg(feature = "buffered")]
+        {
+            use crate::framebuffer::{decode_pixel, encode_pixel};
+
+            assert_eq!(mock.take(), []);
+            let stored = decode_pixel(encode_pixel(expected));
+            assert_eq!(mock::pixel(&display, 3, 4), stored);
+        }
+    }
+
+    #[test]
+    fn color_converted_rounds_to_rgb565() {
+        assert_converted(Rgb888::new(255, 128, 0), 0xFC00);
+        assert_converted(Rgb888::new(4, 2, 3), 0x0000);
+        assert_converted(Rgb888::new(5, 3, 4), 0x0820);
+        assert_converted(Rgb666::new(63, 63, 63), 0xFFFF);
+        assert_converted(Rgb666::new(2, 5, 1), 0x08A0);
+    }
+}
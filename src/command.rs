@@ -1,13 +1,31 @@
+use crate::properties::{ColorMode, DisplayRotation};
 use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
 
-const REMAP_BASE: u8 = 0b00100100;
+/// Vertical address increment, bit 0 of `SetRemap`
+const REMAP_VERTICAL: u8 = 1 << 0;
 
+/// Column address 127 mapped to SEG0, bit 1 of `SetRemap`
+const REMAP_COLUMNS: u8 = 1 << 1;
+
+/// C, B, A colour sequence, bit 2 of `SetRemap`
+const REMAP_SEQUENCE: u8 = 1 << 2;
+
+/// Scan from COM[N-1] to COM0, bit 4 of `SetRemap`
+const REMAP_SCAN: u8 = 1 << 4;
+
+/// Odd/even split of the COM lines, bit 5 of `SetRemap`
+const REMAP_COM_SPLIT: u8 = 1 << 5;
+
+/// Most command bytes `send_batch` collects before sending them
+const BATCH_SIZE: usize = 16;
+
+#[derive(Clone, Copy)]
 pub enum Command {
     /// Column address
     Column(u8, u8),
     /// Row address
     Row(u8, u8),
-    /// CommandLock
+    /// CommandLock, see [`CommandLockMode`](crate::properties::CommandLockMode) for the values
     CommandLock(u8),
     /// DisplayOn
     DisplayOn(bool),
@@ -17,8 +35,8 @@ pub enum Command {
     ClockDiv(u8),
     /// MuxRatio
     MuxRatio(u8),
-    /// SetRemap - horizontal or vertical increment, SegmentRemap, Reverse scan direction
-    SetRemap(bool, bool, bool),
+    /// SetRemap - bits 5:0 as returned by [`remap_byte`], colour depth
+    SetRemap(u8, ColorMode),
     /// Display Start Row
     StartLine(u8),
     /// DisplayOffset
@@ -31,8 +49,14 @@ pub enum Command {
     PreCharge(u8),
     /// Vcomh
     Vcomh(u8),
+    /// Pre-charge voltage level, from 0x00 for 0.20 x VCC to 0x1F for 0.60 x VCC
+    PreChargeVoltage(u8),
     /// NormalDisplayOn
     Invert(bool),
+    /// All pixels off, regardless of RAM contents
+    AllOff,
+    /// All pixels on at the highest grayscale level, regardless of RAM contents
+    AllOn,
     /// Contrast
     Contrast(u8),
     /// ContrastMaster
@@ -41,34 +65,111 @@ pub enum Command {
     SetVsl,
     /// SetPrecharge
     PreCharge2(u8),
+    /// Display enhancement, only available on later SSD1351 revisions
+    DisplayEnhancement(bool),
+    /// Gray scale table, the 63 table bytes have to follow as data
+    GrayScaleTable,
+    /// Restore the built-in linear gray scale table
+    LinearLut,
+    /// No operation
+    Nop,
     // PhaseLength(u8)
 }
 
+/// Get bits 5:0 of the `SetRemap` (`A0h`) argument for `rotation`, optionally mirrored along
+/// the x or y axis of the rotated display and with a BGR instead of an RGB colour sequence. With
+/// neither, this is what [`Display::set_rotation`](crate::display::Display::set_rotation) sends.
+/// The colour depth bits 7:6 are added by `Command::SetRemap`.
+///
+/// Bit 0 selects vertical address increment, used for 90º and 270º, bit 1 maps column 127 to
+/// SEG0, bit 2 the C, B, A colour sequence of RGB panels, bit 4 scans from COM[N-1] to COM0 and
+/// bit 5 splits the COM lines into odd and even, as these panels are wired. Along a vertical
+/// increment the x axis runs over the COM lines, so `mirror_h` flips bit 4 instead of bit 1. Note
+/// that flipping bit 4 also moves which RAM rows are shown on a panel of fewer than 128 rows.
+pub fn remap_byte(rotation: DisplayRotation, mirror_h: bool, mirror_v: bool, bgr: bool) -> u8 {
+    let (vertical, columns, scan) = match rotation {
+        DisplayRotation::Rotate0 => (false, mirror_h, !mirror_v),
+        DisplayRotation::Rotate90 => (true, !mirror_v, !mirror_h),
+        DisplayRotation::Rotate180 => (false, !mirror_h, mirror_v),
+        DisplayRotation::Rotate270 => (true, mirror_v, mirror_h),
+    };
+    let mut remap = REMAP_COM_SPLIT;
+    if vertical {
+        remap |= REMAP_VERTICAL;
+    }
+    if columns {
+        remap |= REMAP_COLUMNS;
+    }
+    if !bgr {
+        remap |= REMAP_SEQUENCE;
+    }
+    if scan {
+        remap |= REMAP_SCAN;
+    }
+    remap
+}
+
 impl Command {
     /// Send command to SSD1351
     pub fn send<DI>(self, iface: &mut DI) -> Result<(), DisplayError>
     where
         DI: WriteOnlyDataCommand,
     {
+        let (command, data, len) = self.encode();
+
+        // Send command over the interface
+        iface.send_commands(DataFormat::U8(&[command]))?;
+
+        if len > 0 {
+            iface.send_data(DataFormat::U8(&data[0..len]))?;
+        }
+
+        Ok(())
+    }
+
+    /// Send several commands with as few transfers as the protocol allows. Command bytes go out
+    /// with DC low and their arguments with DC high, so only consecutive commands without
+    /// arguments (e.g. `Invert`, `DisplayOn`, `WriteRam`) share one transfer, every argument still
+    /// costs a transfer of its own. The bytes on the bus are the same as sending each command in
+    /// turn.
+    pub fn send_batch<DI>(commands: &[Command], iface: &mut DI) -> Result<(), DisplayError>
+    where
+        DI: WriteOnlyDataCommand,
+    {
+        let mut pending = [0u8; BATCH_SIZE];
+        let mut pending_len = 0;
+        for command in commands {
+            let (command, data, len) = command.encode();
+            if pending_len == pending.len() {
+                iface.send_commands(DataFormat::U8(&pending))?;
+                pending_len = 0;
+            }
+            pending[pending_len] = command;
+            pending_len += 1;
+            if len > 0 {
+                iface.send_commands(DataFormat::U8(&pending[..pending_len]))?;
+                iface.send_data(DataFormat::U8(&data[0..len]))?;
+                pending_len = 0;
+            }
+        }
+        if pending_len > 0 {
+            iface.send_commands(DataFormat::U8(&pending[..pending_len]))?;
+        }
+        Ok(())
+    }
+
+    /// The command byte, its arguments and the number of arguments
+    fn encode(self) -> (u8, [u8; 6], usize) {
         // Transform command into a fixed size array of 7 u8 and the real length for sending
         // TODO can we replace the use if the static buffers?
-        let (command, data, len) = match self {
+        match self {
             Command::CommandLock(val) => (0xFD, [val, 0, 0, 0, 0, 0], 1),
             Command::DisplayOn(val) => (if val { 0xAF } else { 0xAE }, [0, 0, 0, 0, 0, 0], 0),
             Command::ClockDiv(val) => (0xB3, [val, 0, 0, 0, 0, 0], 1),
             Command::MuxRatio(val) => (0xCA, [val, 0, 0, 0, 0, 0], 1),
-            Command::SetRemap(incr, remap, scan) => (
-                0xA0,
-                [
-                    REMAP_BASE | (incr as u8) | (remap as u8) << 1 | (scan as u8) << 4,
-                    0,
-                    0,
-                    0,
-                    0,
-                    0,
-                ],
-                1,
-            ),
+            Command::SetRemap(remap, mode) => {
+                (0xA0, [remap & 0x3F | mode.bits() << 6, 0, 0, 0, 0, 0], 1)
+            }
             Command::Column(start, end) => (0x15, [start, end, 0, 0, 0, 0], 2),
             Command::Row(start, end) => (0x75, [start, end, 0, 0, 0, 0], 2),
             Command::StartLine(val) => (0xA1, [val, 0, 0, 0, 0, 0], 1),
@@ -77,22 +178,22 @@ impl Command {
             Command::FunctionSelect(val) => (0xAB, [val, 0, 0, 0, 0, 0], 1),
             Command::PreCharge(val) => (0xB1, [val, 0, 0, 0, 0, 0], 1),
             Command::Vcomh(val) => (0xBE, [val, 0, 0, 0, 0, 0], 1),
+            Command::PreChargeVoltage(val) => (0xBB, [val, 0, 0, 0, 0, 0], 1),
             Command::Invert(val) => (if val { 0xA7 } else { 0xA6 }, [0, 0, 0, 0, 0, 0], 0),
+            Command::AllOff => (0xA4, [0, 0, 0, 0, 0, 0], 0),
+            Command::AllOn => (0xA5, [0, 0, 0, 0, 0, 0], 0),
             Command::Contrast(val) => (0xC1, [0xC8, val, 0xC8, 0, 0, 0], 3),
             Command::ContrastCurrent(val) => (0xC7, [val, 0, 0, 0, 0, 0], 1),
             Command::SetVsl => (0xB4, [0xA0, 0xB5, 0x55, 0, 0, 0], 3),
             Command::PreCharge2(val) => (0xB6, [val, 0, 0, 0, 0, 0], 1),
+            Command::DisplayEnhancement(val) => {
+                (0xB2, [if val { 0xA4 } else { 0x00 }, 0, 0, 0, 0, 0], 3)
+            }
             Command::WriteRam => (0x5C, [0, 0, 0, 0, 0, 0], 0),
-        };
-
-        // Send command over the interface
-        iface.send_commands(DataFormat::U8(&[command]))?;
-
-        if len > 0 {
-            iface.send_data(DataFormat::U8(&data[0..len]))?;
+            Command::GrayScaleTable => (0xB8, [0, 0, 0, 0, 0, 0], 0),
+            Command::LinearLut => (0xB9, [0, 0, 0, 0, 0, 0], 0),
+            Command::Nop => (0xE3, [0, 0, 0, 0, 0, 0], 0),
         }
-
-        Ok(())
     }
 }
 
@@ -162,3 +263,117 @@ impl Command {
 //     Command::Noop => ([0xE3, 0, 0, 0, 0, 0, 0], 1),
 //     Command::ChargePump(en) => ([0x8D, 0x10 | ((en as u8) << 2), 0, 0, 0, 0, 0], 2),
 // };
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockInterface, Transfer};
+
+    /// Every byte of `transfers` with the DC level it was sent at, high for data
+    fn bus(transfers: &[Transfer]) -> Vec<(bool, u8)> {
+        let mut bus = Vec::new();
+        for transfer in transfers {
+            let (dc, bytes) = match transfer {
+                Transfer::Command(bytes) => (false, bytes),
+                Transfer::Data(bytes) => (true, bytes),
+            };
+            bus.extend(bytes.iter().map(|&byte| (dc, byte)));
+        }
+        bus
+    }
+
+    #[test]
+    fn send_batch_matches_sending_each_command() {
+        let mut commands = vec![
+            Command::CommandLock(0x12),
+            Command::DisplayOn(false),
+            Command::Invert(true),
+            Command::Column(0, 127),
+            Command::Row(0, 127),
+            Command::WriteRam,
+        ];
+        // More commands without arguments in a row than fit in one batch
+        commands.extend([Command::Nop; BATCH_SIZE + 3]);
+        commands.extend([
+            Command::Contrast(0x80),
+            Command::AllOn,
+            Command::DisplayOn(true),
+        ]);
+
+        let mut single = MockInterface::default();
+        for &command in &commands {
+            command.send(&mut single).unwrap();
+        }
+        let mut batched = MockInterface::default();
+        Command::send_batch(&commands, &mut batched).unwrap();
+
+        let (single, batched) = (single.take(), batched.take());
+        assert_eq!(bus(&batched), bus(&single));
+        assert!(batched.len() < single.len());
+        assert!(batched.iter().all(|transfer| match transfer {
+            Transfer::Command(bytes) => !bytes.is_empty() && bytes.len() <= BATCH_SIZE,
+            Transfer::Data(bytes) => !bytes.is_empty(),
+        }));
+        // Every argument follows its own command byte in the transfer before it
+        for (i, transfer) in batched.iter().enumerate() {
+            if let Transfer::Data(_) = transfer {
+                assert!(matches!(batched[i - 1], Transfer::Command(_)));
+            }
+        }
+    }
+
+    #[test]
+    fn remap_byte_covers_every_rotation_and_mirror() {
+        // Indexed by mirror_h | mirror_v << 1 | bgr << 2
+        let table = [
+            (
+                DisplayRotation::Rotate0,
+                [0x34, 0x36, 0x24, 0x26, 0x30, 0x32, 0x20, 0x22],
+            ),
+            (
+                DisplayRotation::Rotate90,
+                [0x37, 0x27, 0x35, 0x25, 0x33, 0x23, 0x31, 0x21],
+            ),
+            (
+                DisplayRotation::Rotate180,
+                [0x26, 0x24, 0x36, 0x34, 0x22, 0x20, 0x32, 0x30],
+            ),
+            (
+                DisplayRotation::Rotate270,
+                [0x25, 0x35, 0x27, 0x37, 0x21, 0x31, 0x23, 0x33],
+            ),
+        ];
+        for (r, (rotation, expected)) in table.into_iter().enumerate() {
+            for (i, &remap) in expected.iter().enumerate() {
+                let (mirror_h, mirror_v, bgr) = (i & 1 != 0, i & 2 != 0, i & 4 != 0);
+                assert_eq!(
+                    remap_byte(rotation, mirror_h, mirror_v, bgr),
+                    remap,
+                    "rotation {r} mirror_h {mirror_h} mirror_v {mirror_v} bgr {bgr}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unmirrored_remap_matches_the_old_set_remap() {
+        // The argument `SetRemap(incr, remap, scan, mode)` sent before `remap_byte` existed
+        fn old(incr: bool, remap: bool, scan: bool, mode: ColorMode) -> u8 {
+            0b0010_0100 | incr as u8 | (remap as u8) << 1 | (scan as u8) << 4 | mode.bits() << 6
+        }
+        let table = [
+            (DisplayRotation::Rotate0, (false, false, true)),
+            (DisplayRotation::Rotate90, (true, true, true)),
+            (DisplayRotation::Rotate180, (false, true, false)),
+            (DisplayRotation::Rotate270, (true, false, false)),
+        ];
+        for (r, (rotation, (incr, remap, scan))) in table.into_iter().enumerate() {
+            for mode in [ColorMode::Rgb565, ColorMode::Rgb666] {
+                let (command, data, len) =
+                    Command::SetRemap(remap_byte(rotation, false, false, false), mode).encode();
+                assert_eq!((command, len), (0xA0, 1));
+                assert_eq!(data[0], old(incr, remap, scan, mode), "rotation {r}");
+            }
+        }
+    }
+}
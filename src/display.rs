@@ -1,19 +1,188 @@
 //! Container to store and set display properties
 
-use crate::command::Command;
+use crate::command::{remap_byte, Command};
 
+use crate::properties::Capabilities;
+use crate::properties::ColorMode;
+use crate::properties::CommandLockMode;
+use crate::properties::DisplayProfile;
 use crate::properties::DisplayRotation;
 use crate::properties::DisplaySize;
+use crate::properties::GpioMode;
+use crate::properties::Region;
+use crate::properties::TestPattern;
 
+use crate::error::Error;
 use display_interface::DataFormat;
 use display_interface::DisplayError;
 use display_interface::WriteOnlyDataCommand;
+use hal::delay::DelayNs;
+
+/// Number of bytes sent per transfer when clearing the display
+const CLEAR_CHUNK_SIZE: usize = 256;
+
+/// Number of pixels widened to 6 bits per channel per transfer in `ColorMode::Rgb666`
+const RGB666_CHUNK_PIXELS: usize = 64;
+
+/// Largest data alignment accepted by `set_data_alignment`, in bytes
+const MAX_DATA_ALIGNMENT: usize = 8;
+
+/// Draw window given as `(start, end)` column/row corners, the end being exclusive
+pub type DrawArea = ((u8, u8), (u8, u8));
+
+/// Front clock divider and oscillator frequency set by `init`
+const CLOCK_DIV: u8 = 0xF1;
+
+/// Front clock divider of 1 at the same oscillator frequency, see
+/// [`Display::optimize_for_low_brightness`]
+const LOW_BRIGHTNESS_CLOCK_DIV: u8 = 0xF0;
+
+/// Phase 1 and phase 2 lengths set by `init`
+const PHASE_LENGTH: u8 = 0x32;
+
+/// DCLKs spent in the current drive period of each row
+const CURRENT_DRIVE_DCLKS: u64 = 134;
+
+/// Time each step of the self test stays on screen
+const SELF_TEST_STEP_MS: u32 = 500;
+
+/// Gray scale pulse widths for the levels GS1 to GS63, see [`Display::set_gamma`]
+pub type GammaTable = [u8; 63];
+
+/// Longest gray scale pulse width accepted by the display, in DCLKs
+const MAX_GAMMA_SETTING: u8 = 180;
+
+/// Highest master contrast level
+const MAX_MASTER_CONTRAST: u8 = 0x0F;
+
+/// Pre-charge voltage level after reset, about 0.50 x VCC
+const PRECHARGE_VOLTAGE: u8 = 0x17;
+
+/// Highest pre-charge voltage level, 0.60 x VCC
+const MAX_PRECHARGE_VOLTAGE: u8 = 0x1F;
+
+/// Second pre-charge period sent by `init`, in DCLKs
+const SECOND_PRECHARGE: u8 = 0x01;
+
+/// Longest second pre-charge period, in DCLKs
+const MAX_SECOND_PRECHARGE: u8 = 0x0F;
+
+/// Settings that are sent by `init` and re-sent by `reinit_config`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DisplayConfig {
+    /// Display enhancement mode, see [`Display::set_display_enhancement`]
+    pub display_enhancement: bool,
+    /// Modes of the two GPIO pins, see [`Display::set_gpio`]
+    pub gpio: (GpioMode, GpioMode),
+    /// Custom gray scale table, `None` for the built-in linear one, see [`Display::set_gamma`]
+    pub gamma: Option<GammaTable>,
+    /// Master contrast from 0 to 15, see [`Display::set_master_contrast`]
+    pub master_contrast: u8,
+    /// Number of rows driven, `None` for the full panel height, see [`Display::set_active_rows`]
+    pub active_rows: Option<u8>,
+    /// Colour depth of the display RAM, see [`Display::set_color_mode`]
+    pub color_mode: ColorMode,
+    /// Front clock divider and oscillator frequency, see [`Display::optimize_for_low_brightness`]
+    pub clock_div: u8,
+    /// Pre-charge voltage level from 0 to 31, see [`Display::set_precharge_voltage_level`]
+    pub precharge_voltage: u8,
+    /// Second pre-charge period from 1 to 15 DCLKs, see [`Display::set_second_precharge`]
+    pub second_precharge: u8,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        DisplayConfig {
+            display_enhancement: false,
+            gpio: (GpioMode::InputDisabled, GpioMode::InputDisabled),
+            gamma: None,
+            master_contrast: MAX_MASTER_CONTRAST,
+            active_rows: None,
+            color_mode: ColorMode::Rgb565,
+            clock_div: CLOCK_DIV,
+            precharge_voltage: PRECHARGE_VOLTAGE,
+            second_precharge: SECOND_PRECHARGE,
+        }
+    }
+}
+
+/// Commands sent and time spent in one phase of `init`, see [`Display::init_instrumented`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PhaseStats {
+    /// Number of commands, not counting the data following them
+    pub commands: u16,
+    /// Time spent in microseconds
+    pub elapsed_us: u32,
+}
+
+/// Breakdown of `init` into its phases, see [`Display::init_instrumented`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InitStats {
+    /// Unlocking the command interface and turning the display off
+    pub unlock: PhaseStats,
+    /// Sending the configuration, see [`DisplayConfig`]
+    pub config: PhaseStats,
+    /// Clearing the display RAM
+    pub clear: PhaseStats,
+    /// Turning the display back on
+    pub display_on: PhaseStats,
+}
+
+impl InitStats {
+    /// Total number of commands sent
+    pub fn commands(&self) -> u16 {
+        self.unlock.commands + self.config.commands + self.clear.commands + self.display_on.commands
+    }
+
+    /// Total time spent in microseconds
+    pub fn elapsed_us(&self) -> u32 {
+        self.unlock.elapsed_us
+            + self.config.elapsed_us
+            + self.clear.elapsed_us
+            + self.display_on.elapsed_us
+    }
+}
+
+/// The steps of `init` in the order they run, see [`InitStats`]
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum InitPhase {
+    Unlock,
+    Config,
+    Clear,
+    DisplayOn,
+}
+
+impl InitPhase {
+    const ALL: [InitPhase; 4] = [
+        InitPhase::Unlock,
+        InitPhase::Config,
+        InitPhase::Clear,
+        InitPhase::DisplayOn,
+    ];
+}
 
 /// Display properties struct
 pub struct Display<DI> {
     iface: DI,
     display_size: DisplaySize,
     display_rotation: DisplayRotation,
+    config: DisplayConfig,
+    /// Settings last accepted by the interface
+    applied_config: DisplayConfig,
+    /// Profile last applied through `apply_profile`
+    profile: Option<DisplayProfile>,
+    reset_active_low: bool,
+    draw_area_caching: bool,
+    /// Number of bytes the length of every data transfer is padded to a multiple of
+    data_alignment: u8,
+    /// Last programmed draw window
+    draw_area: Option<DrawArea>,
+    /// Bytes written into the draw window since the RAM position was last at its start
+    draw_offset: usize,
+    /// Most recent error of the interface
+    last_error: Option<DisplayError>,
+    /// Number of commands sent so far, wrapping around, for `init_instrumented`
+    commands_sent: u16,
 }
 
 impl<DI> Display<DI>
@@ -30,6 +199,16 @@ where
             iface,
             display_size,
             display_rotation,
+            config: DisplayConfig::default(),
+            applied_config: DisplayConfig::default(),
+            profile: None,
+            reset_active_low: true,
+            draw_area_caching: true,
+            data_alignment: 1,
+            draw_area: None,
+            draw_offset: 0,
+            last_error: None,
+            commands_sent: 0,
         }
     }
 
@@ -38,68 +217,804 @@ where
         self.iface
     }
 
+    /// Access the interface directly, e.g. to send a vendor command that the driver does not
+    /// model without releasing the display. The driver cannot see what is sent this way: anything
+    /// moving the draw window or the RAM position has to be followed by
+    /// [`invalidate_draw_area`](Self::invalidate_draw_area), and settings changed behind its back
+    /// are overwritten by the next `init` or `reinit_config`.
+    pub fn interface_mut(&mut self) -> &mut DI {
+        &mut self.iface
+    }
+
     /// Initialise the display in column mode (i.e. a byte walks down a column of 8 pixels) with
     /// column 0 on the left and column _(display_width - 1)_ on the right.
-    pub fn init(&mut self) -> Result<(), DisplayError> {
-        let (_, display_height) = self.display_size.dimensions();
+    pub fn init(&mut self) -> Result<(), Error> {
+        self.init_inner(true, None)
+    }
 
-        // TODO: Break up into nice bits so display modes can pick whathever they need
-        Command::CommandLock(0x12).send(&mut self.iface)?;
-        Command::CommandLock(0xB1).send(&mut self.iface)?;
-        Command::DisplayOn(false).send(&mut self.iface)?;
-        Command::ClockDiv(0xF1).send(&mut self.iface)?;
-        Command::MuxRatio(display_height - 1).send(&mut self.iface)?;
-        Command::DisplayOffset(0).send(&mut self.iface)?;
-        Command::StartLine(0).send(&mut self.iface)?;
-        Command::SetGpio(0x00).send(&mut self.iface)?;
-        Command::FunctionSelect(0x01).send(&mut self.iface)?;
-        Command::SetVsl.send(&mut self.iface)?;
-        Command::Contrast(0x8F).send(&mut self.iface)?;
-        Command::ContrastCurrent(0x0F).send(&mut self.iface)?;
-        // Command::PhaseLength(0x32).send(&mut self.iface)?;
-        // Command::PreCharge(0x17).send(&mut self.iface)?;
-        Command::PreCharge(0x32).send(&mut self.iface)?;
-        Command::PreCharge2(0x01).send(&mut self.iface)?;
-        Command::Vcomh(0x05).send(&mut self.iface)?;
-        Command::Invert(false).send(&mut self.iface)?;
+    /// Initialise the display like `init`, but without clearing it. Clearing sends a full frame of
+    /// zeroes, 32 KiB on a 128x128 display or roughly 65 ms on a 4 MHz SPI bus, which is wasted
+    /// when the whole screen gets drawn right after. Until then the display shows whatever is left
+    /// in its RAM, which is random after power up.
+    pub fn init_no_clear(&mut self) -> Result<(), Error> {
+        self.init_inner(false, None)
+    }
 
-        self.set_rotation(self.display_rotation).unwrap();
+    /// Initialise the display like `init` and wait `settle_ms` milliseconds before returning. The
+    /// datasheet has the SEG and COM drivers turn on 200 ms after the `DisplayOn` command, frames
+    /// sent before that may not show correctly, so 200 is the recommended value.
+    pub fn init_with_delay<DELAY>(&mut self, delay: &mut DELAY, settle_ms: u32) -> Result<(), Error>
+    where
+        DELAY: DelayNs,
+    {
+        self.init_inner(true, None)?;
+        delay.delay_ms(settle_ms);
+        Ok(())
+    }
 
-        self.clear()?;
+    /// Initialise the display like `init`, calling `feed` between its phases and after every
+    /// chunk of the clear, e.g. to pet a watchdog that would trip over the full frame of zeroes on
+    /// a slow bus. No single transfer in between is longer than 256 bytes.
+    pub fn init_chunked<F>(&mut self, mut feed: F) -> Result<(), Error>
+    where
+        F: FnMut(),
+    {
+        self.init_inner(true, Some(&mut feed))
+    }
 
-        Command::DisplayOn(true).send(&mut self.iface)?;
+    /// Re-send the configuration to recover from a brown-out, which can corrupt the registers of
+    /// the panel while its RAM survives. Unlike `init` this neither clears the display nor turns
+    /// it off in between, so the image on screen is kept.
+    pub fn reinit_config(&mut self) -> Result<(), Error> {
+        // The window registers may be as corrupt as the rest, re-program it on the next draw
+        self.invalidate_draw_area();
+
+        self.unlock()?;
+        self.send_config()?;
+        self.command(Command::DisplayOn(true))?;
+        Ok(())
+    }
+
+    /// Initialise the display like `init`, counting the commands sent and timing each phase with
+    /// `now_us`, which returns a timestamp in microseconds (e.g. from a free running timer), to
+    /// find out which step stalls on a slow bus. `init` itself does not pay for any of this.
+    pub fn init_instrumented<F>(&mut self, mut now_us: F) -> Result<InitStats, Error>
+    where
+        F: FnMut() -> u32,
+    {
+        self.invalidate_draw_area();
+        self.init_phases(&mut now_us)
+    }
+
+    fn init_inner(&mut self, clear: bool, mut feed: Option<&mut dyn FnMut()>) -> Result<(), Error> {
+        self.invalidate_draw_area();
+
+        for phase in InitPhase::ALL {
+            if phase != InitPhase::Clear || clear {
+                self.init_phase(phase, feed.as_mut().map(|feed| &mut **feed as _))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Unlock the command interface, including the commands locked after a reset
+    fn unlock(&mut self) -> Result<(), Error> {
+        self.set_command_lock(CommandLockMode::Unlock)?;
+        self.set_command_lock(CommandLockMode::EnableProtected)
+    }
+
+    /// Lock or unlock the command interface, or change which commands are accessible while it is
+    /// unlocked. `init` unlocks it and enables the protected commands, which the configuration
+    /// needs. While locked, every draw and setting is silently ignored by the panel until
+    /// `CommandLockMode::Unlock` is sent.
+    pub fn set_command_lock(&mut self, mode: CommandLockMode) -> Result<(), Error> {
+        self.command(Command::CommandLock(mode.bits()))?;
+        Ok(())
+    }
+
+    /// Send every configuration command of `init`, leaving RAM and the draw window alone
+    fn send_config(&mut self) -> Result<(), Error> {
+        let (gpio0, gpio1) = self.config.gpio;
+
+        self.command(Command::ClockDiv(self.config.clock_div))?;
+        self.command(Command::MuxRatio(self.active_rows() - 1))?;
+        self.command(Command::DisplayOffset(0))?;
+        // The start line depends on the rotation and is sent by `set_rotation` below
+        self.command(Command::SetGpio(gpio1.bits() << 2 | gpio0.bits()))?;
+        self.command(Command::FunctionSelect(0x01))?;
+        self.command(Command::SetVsl)?;
+        self.command(Command::Contrast(0x8F))?;
+        self.command(Command::ContrastCurrent(self.config.master_contrast))?;
+        // self.command(Command::PhaseLength(0x32))?;
+        // self.command(Command::PreCharge(0x17))?;
+        self.command(Command::PreCharge(PHASE_LENGTH))?;
+        self.command(Command::PreCharge2(self.config.second_precharge))?;
+        self.command(Command::PreChargeVoltage(self.config.precharge_voltage))?;
+        self.command(Command::Vcomh(0x05))?;
+        self.command(Command::Invert(false))?;
+        if self.config.display_enhancement {
+            self.command(Command::DisplayEnhancement(true))?;
+        }
+
+        if let Some(table) = self.config.gamma {
+            self.send_gamma(&table)?;
+        }
+
+        self.set_rotation(self.display_rotation)?;
+        self.applied_config = self.config;
 
         Ok(())
     }
 
     /// Clear the display by setting all pixels to black
-    pub fn clear(&mut self) -> Result<(), DisplayError> {
+    pub fn clear(&mut self) -> Result<(), Error> {
+        self.clear_with(0x0000, || {})
+    }
+
+    /// Set all pixels of the display to the RGB565 `color`, in chunks like `clear`
+    pub fn clear_to(&mut self, color: u16) -> Result<(), Error> {
+        self.clear_with(color, || {})
+    }
+
+    /// Set all pixels of the display to `color` like `clear_to`, calling `feed` after every chunk
+    fn clear_with<F>(&mut self, color: u16, mut feed: F) -> Result<(), Error>
+    where
+        F: FnMut(),
+    {
+        let (display_width, _) = self.display_size.dimensions();
+        let display_height = self.active_rows();
+        self.set_draw_area((0, 0), (display_width, display_height))?;
+
+        // Send the pixels in chunks rather than one by one, every `send_data` call has a fixed
+        // overhead (DC toggling, bus locking) which dominates on fast parallel buses
+        let mut chunk = [0x00u8; CLEAR_CHUNK_SIZE];
+        for pixel in chunk.chunks_exact_mut(2) {
+            pixel.copy_from_slice(&color.to_be_bytes());
+        }
+        let mut remaining = display_height as usize * display_width as usize * 2;
+        while remaining > 0 {
+            let len = remaining.min(chunk.len());
+            self.draw(&chunk[..len])?;
+            remaining -= len;
+            feed();
+        }
+        Ok(())
+    }
+
+    /// Enable or disable the display enhancement mode (command `0xB2`), which improves contrast
+    /// at low drive currents. The setting is remembered and reapplied by `init`.
+    ///
+    /// This command is only documented from revision 1.x of the SSD1351 datasheet onwards, it is
+    /// missing from earlier revisions (such as the Rev 0.10 in `docs/`) and panels built on them
+    /// may not accept it. It is therefore never sent unless enabled here.
+    pub fn set_display_enhancement(&mut self, enable: bool) -> Result<(), Error> {
+        self.config.display_enhancement = enable;
+        self.command(Command::DisplayEnhancement(enable))?;
+        self.applied_config.display_enhancement = enable;
+        Ok(())
+    }
+
+    /// Set the master contrast, which scales the current of all colours at once, from 0 to 15.
+    /// The setting is kept and re-applied by `init`, it starts out at the maximum of 15.
+    ///
+    /// Returns `Error::OutOfBounds` for a contrast above 15.
+    pub fn set_master_contrast(&mut self, contrast: u8) -> Result<(), Error> {
+        if contrast > MAX_MASTER_CONTRAST {
+            return Err(Error::OutOfBounds);
+        }
+        self.config.master_contrast = contrast;
+        self.command(Command::ContrastCurrent(contrast))?;
+        self.applied_config.master_contrast = contrast;
+        Ok(())
+    }
+
+    /// Set the pre-charge voltage (command `0xBB`) from 0 for 0.20 x VCC to 31 for 0.60 x VCC in
+    /// even steps. It starts out at the reset value of 23, about 0.50 x VCC. Raising it helps
+    /// panels whose columns differ in brightness, mostly at low gray levels, lowering it makes
+    /// blacks darker. The setting is kept and re-applied by `init`.
+    ///
+    /// Returns `Error::OutOfBounds` for a level above 31.
+    pub fn set_precharge_voltage_level(&mut self, level: u8) -> Result<(), Error> {
+        if level > MAX_PRECHARGE_VOLTAGE {
+            return Err(Error::OutOfBounds);
+        }
+        self.config.precharge_voltage = level;
+        self.command(Command::PreChargeVoltage(level))?;
+        self.applied_config.precharge_voltage = level;
+        Ok(())
+    }
+
+    /// Set the second pre-charge period (command `0xB6`) from 1 to 15 DCLKs. During it the
+    /// columns are driven towards the pre-charge voltage before each row is lit, a longer period
+    /// charges the pixel capacitance more fully and reduces crosstalk between neighbouring columns
+    /// at the cost of a little brightness. `init` sets it to 1 DCLK, the setting is kept and
+    /// re-applied by `init`.
+    ///
+    /// Returns `Error::OutOfBounds` for a period of 0 or above 15.
+    pub fn set_second_precharge(&mut self, period: u8) -> Result<(), Error> {
+        if period == 0 || period > MAX_SECOND_PRECHARGE {
+            return Err(Error::OutOfBounds);
+        }
+        self.config.second_precharge = period;
+        self.command(Command::PreCharge2(period))?;
+        self.applied_config.second_precharge = period;
+        Ok(())
+    }
+
+    /// Set the master contrast, the pre-charge voltage and the second pre-charge period together
+    /// to the values of `profile`, see [`DisplayProfile`] for what each one sets. Like the single
+    /// settings they are kept and re-applied by `init`, and can be fine tuned one by one
+    /// afterwards.
+    pub fn apply_profile(&mut self, profile: DisplayProfile) -> Result<(), Error> {
+        let (master_contrast, precharge_voltage, second_precharge) = profile.settings();
+        self.set_master_contrast(master_contrast)?;
+        self.set_precharge_voltage_level(precharge_voltage)?;
+        self.set_second_precharge(second_precharge)?;
+        self.profile = Some(profile);
+        Ok(())
+    }
+
+    /// Get the profile last applied through [`apply_profile`](Self::apply_profile), even if some
+    /// of its settings were changed since. `None` until a profile is applied.
+    pub fn profile(&self) -> Option<DisplayProfile> {
+        self.profile
+    }
+
+    /// Double the frame rate to cut down on flicker at a low master contrast, where the frame rate
+    /// set by `init` becomes visible more easily. This drops the front clock divider of `B3h` from
+    /// 2 to 1 and keeps the oscillator at its highest frequency, which is as fast as the SSD1351
+    /// goes, see [`frame_period_ns`](Self::frame_period_ns). It costs some power, and the
+    /// precharge periods are left alone as they are given in DCLKs and scale along. The setting is
+    /// kept and re-applied by `init`, [`optimize_for_normal`](Self::optimize_for_normal) reverts
+    /// it.
+    pub fn optimize_for_low_brightness(&mut self) -> Result<(), Error> {
+        self.config.clock_div = LOW_BRIGHTNESS_CLOCK_DIV;
+        self.command(Command::ClockDiv(LOW_BRIGHTNESS_CLOCK_DIV))?;
+        self.applied_config.clock_div = LOW_BRIGHTNESS_CLOCK_DIV;
+        Ok(())
+    }
+
+    /// Restore the front clock divider set by `init`, undoing
+    /// [`optimize_for_low_brightness`](Self::optimize_for_low_brightness)
+    pub fn optimize_for_normal(&mut self) -> Result<(), Error> {
+        self.config.clock_div = CLOCK_DIV;
+        self.command(Command::ClockDiv(CLOCK_DIV))?;
+        self.applied_config.clock_div = CLOCK_DIV;
+        Ok(())
+    }
+
+    /// Ramp the master contrast from its current value to `target_contrast` in `steps` steps of
+    /// `step_ms` milliseconds each, e.g. to fade the display in or out. With only 16 levels, steps
+    /// that don't change the level just wait.
+    ///
+    /// Returns `Error::OutOfBounds` for a target above 15.
+    pub fn fade_to<DELAY>(
+        &mut self,
+        target_contrast: u8,
+        steps: u16,
+        delay: &mut DELAY,
+        step_ms: u32,
+    ) -> Result<(), Error>
+    where
+        DELAY: DelayNs,
+    {
+        if target_contrast > MAX_MASTER_CONTRAST {
+            return Err(Error::OutOfBounds);
+        }
+        let start = self.config.master_contrast as i32;
+        let distance = target_contrast as i32 - start;
+        let steps = steps.max(1) as i32;
+
+        for step in 1..=steps {
+            let contrast = (start + distance * step / steps) as u8;
+            if contrast != self.config.master_contrast {
+                self.set_master_contrast(contrast)?;
+            }
+            delay.delay_ms(step_ms);
+        }
+        Ok(())
+    }
+
+    /// Upload a custom gray scale table for gamma correction. Each entry is the pulse width of one
+    /// gray scale level from GS1 to GS63 in DCLKs. The table is kept and re-applied by `init`.
+    ///
+    /// The datasheet requires the entries to be strictly increasing and at most 180, a table
+    /// breaking either rule can leave the panel blank or garbled. Such a table is rejected with
+    /// `Error::InvalidData` before anything is sent.
+    pub fn set_gamma(&mut self, table: &GammaTable) -> Result<(), Error> {
+        let increasing = table.windows(2).all(|pair| pair[0] < pair[1]);
+        if !increasing || table[table.len() - 1] > MAX_GAMMA_SETTING {
+            return Err(Error::InvalidData);
+        }
+        self.config.gamma = Some(*table);
+        self.send_gamma(table)?;
+        self.applied_config.gamma = Some(*table);
+        Ok(())
+    }
+
+    /// Go back to the built-in linear gray scale table
+    pub fn set_linear_gamma(&mut self) -> Result<(), Error> {
+        self.config.gamma = None;
+        self.command(Command::LinearLut)?;
+        self.applied_config.gamma = None;
+        Ok(())
+    }
+
+    fn send_gamma(&mut self, table: &GammaTable) -> Result<(), Error> {
+        self.command(Command::GrayScaleTable)?;
+        self.data(table)?;
+        Ok(())
+    }
+
+    /// Configure the two GPIO pins of the panel, which some boards wire to a backlight or the reset
+    /// of a touch controller. Both pins are disabled inputs by default. The setting is kept and
+    /// re-applied by `init`.
+    ///
+    /// Returns `Error::Unsupported` if the panel has no GPIO pins.
+    pub fn set_gpio(&mut self, pin0: GpioMode, pin1: GpioMode) -> Result<(), Error> {
+        if !self.capabilities().contains(Capabilities::GPIO) {
+            return Err(Error::Unsupported);
+        }
+        self.config.gpio = (pin0, pin1);
+        self.command(Command::SetGpio(pin1.bits() << 2 | pin0.bits()))?;
+        self.applied_config.gpio = (pin0, pin1);
+        Ok(())
+    }
+
+    /// Only drive the top `rows` rows of the display RAM by lowering the MUX ratio, e.g. to save
+    /// power when just a strip of the panel is used. The rows below stay dark and are left out of
+    /// every draw window, `clear` included. With a 90º or 270º rotation the rows of the display
+    /// RAM are the columns on screen, which [`display_bounds`](Self::display_bounds) takes into
+    /// account. The setting is kept and re-applied by `init`.
+    ///
+    /// Returns `Error::OutOfBounds` for zero rows or more rows than the panel has.
+    pub fn set_active_rows(&mut self, rows: u8) -> Result<(), Error> {
+        let (_, display_height) = self.display_size.dimensions();
+        if rows == 0 || rows > display_height {
+            return Err(Error::OutOfBounds);
+        }
+        self.config.active_rows = match rows {
+            rows if rows == display_height => None,
+            rows => Some(rows),
+        };
+        self.command(Command::MuxRatio(rows - 1))?;
+        self.command(Command::StartLine(self.start_line()))?;
+        self.applied_config.active_rows = self.config.active_rows;
+        Ok(())
+    }
+
+    /// Get the number of rows of the display RAM that are driven, see
+    /// [`set_active_rows`](Self::set_active_rows)
+    pub fn active_rows(&self) -> u8 {
+        self.config
+            .active_rows
+            .unwrap_or(self.display_size.dimensions().1)
+    }
+
+    /// Switch the colour depth of the display RAM between 65k and 262k colours. The driver keeps
+    /// taking RGB565 everywhere, the framebuffer (if any) and the data of `draw` included: in
+    /// `ColorMode::Rgb666` every pixel is widened to three bytes of 6 bits on its way out, which
+    /// costs a copy and half again the bus time. Red and blue gain a low bit from their top one,
+    /// so the full range of the panel is used. The setting is kept and re-applied by `init`.
+    ///
+    /// The display RAM is not converted, redraw the screen after switching. Only 8-bit and serial
+    /// buses are supported, which take the 6 bit channels in separate bytes.
+    ///
+    /// Returns `Error::Unsupported` for `ColorMode::Rgb666` if the panel lacks 262k colours.
+    pub fn set_color_mode(&mut self, mode: ColorMode) -> Result<(), Error> {
+        if mode == ColorMode::Rgb666 && !self.capabilities().contains(Capabilities::COLOR_18BIT) {
+            return Err(Error::Unsupported);
+        }
+        self.config.color_mode = mode;
+        self.set_rotation(self.display_rotation)?;
+        self.applied_config.color_mode = mode;
+        Ok(())
+    }
+
+    /// Get the colour depth set through [`set_color_mode`](Self::set_color_mode)
+    pub fn color_mode(&self) -> ColorMode {
+        self.config.color_mode
+    }
+
+    /// Run a visual self test of the panel, pausing between each step:
+    ///
+    /// 1. every pixel lit at full white, regardless of the RAM contents
+    /// 2. every pixel off
+    /// 3. three colour bars (red, green, blue) written to RAM and shown normally
+    ///
+    /// Returns `Ok` if every command was accepted by the interface. A dead panel or a broken
+    /// connection can only be spotted visually, so watch the panel while this runs. The colour bars
+    /// remain in display RAM afterwards: redraw (or `flush` in buffered mode) to restore the
+    /// previous content.
+    pub fn self_test<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), Error>
+    where
+        DELAY: DelayNs,
+    {
+        self.command(Command::AllOn)?;
+        delay.delay_ms(SELF_TEST_STEP_MS);
+        self.command(Command::AllOff)?;
+        delay.delay_ms(SELF_TEST_STEP_MS);
+
         let (display_width, display_height) = self.display_size.dimensions();
+        let bar_width = display_width.div_ceil(3);
+        let mut row = [0u8; 256];
+        for (x, pixel) in row
+            .chunks_exact_mut(2)
+            .take(display_width as usize)
+            .enumerate()
+        {
+            let color: u16 = match x as u8 / bar_width {
+                0 => 0xF800,
+                1 => 0x07E0,
+                _ => 0x001F,
+            };
+            pixel.copy_from_slice(&color.to_be_bytes());
+        }
+
         self.set_draw_area((0, 0), (display_width, display_height))?;
-        for _ in 0..(display_height as u32 * display_width as u32) {
-            self.iface.send_data(DataFormat::U8(&[0x00, 0x00]))?; // send 8 * 2 bits
+        for _ in 0..self.active_rows() {
+            self.draw(&row[..display_width as usize * 2])?;
         }
+        self.command(Command::Invert(false))?;
+        delay.delay_ms(SELF_TEST_STEP_MS);
+
         Ok(())
     }
 
+    /// Run [`self_test`](Self::self_test), then show `pattern` for another step
+    pub fn self_test_with_pattern<DELAY>(
+        &mut self,
+        delay: &mut DELAY,
+        pattern: TestPattern,
+    ) -> Result<(), Error>
+    where
+        DELAY: DelayNs,
+    {
+        self.self_test(delay)?;
+        self.draw_test_pattern(pattern)?;
+        delay.delay_ms(SELF_TEST_STEP_MS);
+
+        Ok(())
+    }
+
+    /// Fill the visible area with a test pattern in the current orientation, sent row by row
+    /// through a single window
+    pub fn draw_test_pattern(&mut self, pattern: TestPattern) -> Result<(), Error> {
+        let bounds = self.display_bounds();
+        let mut row = [0u8; 256];
+        self.set_draw_area_logical((0, 0), (bounds.width, bounds.height))?;
+        for y in 0..bounds.height {
+            for (x, pixel) in row
+                .chunks_exact_mut(2)
+                .take(bounds.width as usize)
+                .enumerate()
+            {
+                let color = pattern.color(x as u8, y, bounds.width, bounds.height);
+                pixel.copy_from_slice(&color.to_be_bytes());
+            }
+            self.draw(&row[..bounds.width as usize * 2])?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the configuration that `init` and `reinit_config` send
+    pub fn config(&self) -> &DisplayConfig {
+        &self.config
+    }
+
+    /// Get the configuration as last sent to the panel. Every setting is updated once its command
+    /// has been accepted by the interface, `init` and `reinit_config` update all of them. It only
+    /// differs from [`config`](Self::config) after a command failed to go out, which can be put
+    /// right with `reinit_config`. The panel registers cannot be read back, so this is what the
+    /// driver sent rather than what the panel holds. Before `init` it holds the defaults.
+    pub fn applied_config(&self) -> &DisplayConfig {
+        &self.applied_config
+    }
+
+    /// Get the most recent error of the interface, e.g. to log an intermittent bus fault at a
+    /// higher level than where it was returned. It is kept until
+    /// [`clear_last_error`](Self::clear_last_error), later successful transfers leave it in place.
+    /// Transfers through [`interface_mut`](Self::interface_mut) are not seen by the driver.
+    pub fn last_error(&self) -> Option<DisplayError> {
+        self.last_error.clone()
+    }
+
+    /// Forget the error returned by [`last_error`](Self::last_error)
+    pub fn clear_last_error(&mut self) {
+        self.last_error = None;
+    }
+
     /// Set the position in the framebuffer of the display where any sent data should be
     /// drawn. This method can be used for changing the affected area on the screen as well
     /// as (re-)setting the start point of the next `draw` call.
-    pub fn set_draw_area(&mut self, start: (u8, u8), end: (u8, u8)) -> Result<(), DisplayError> {
-        Command::Column(start.0, end.0.saturating_sub(1)).send(&mut self.iface)?;
-        Command::Row(start.1, end.1.saturating_sub(1)).send(&mut self.iface)?;
-        Command::WriteRam.send(&mut self.iface)?;
+    ///
+    /// The area is clamped to the display and its [`active_rows`](Self::active_rows), the window
+    /// that was actually programmed is returned in the same `(start, end)` form with an exclusive
+    /// end, so that `(end.0 - start.0) * (end.1 - start.1) * 2` is the number of bytes it holds.
+    ///
+    /// If the window is the same as the last one and the data sent since then filled it up
+    /// completely, the RAM position is already back at its start and only `WriteRam` is sent.
+    pub fn set_draw_area(&mut self, start: (u8, u8), end: (u8, u8)) -> Result<DrawArea, Error> {
+        let column_end = end.0.saturating_sub(1).min(self.display_size.max_column());
+        let row_end = end.1.saturating_sub(1).min(self.active_rows() - 1);
+        let column_start = start.0.min(column_end);
+        let row_start = start.1.min(row_end);
+        let area = ((column_start, row_start), (column_end + 1, row_end + 1));
+
+        let cached =
+            self.draw_area_caching && self.draw_area == Some(area) && self.draw_offset == 0;
+        if !cached {
+            self.draw_area = None;
+            self.command(Command::Column(column_start, column_end))?;
+            self.command(Command::Row(row_start, row_end))?;
+            self.draw_area = Some(area);
+            self.draw_offset = 0;
+        }
+        self.command(Command::WriteRam)?;
+        Ok(area)
+    }
+
+    /// Set the draw area like `set_draw_area`, but with `start` and the exclusive `end` given as
+    /// `(x, y)` in the current orientation of the display. For 90º and 270º rotations columns and
+    /// rows are swapped before the window is programmed. The returned window is in display RAM
+    /// addresses, like the one of `set_draw_area`.
+    pub fn set_draw_area_logical(
+        &mut self,
+        start: (u8, u8),
+        end: (u8, u8),
+    ) -> Result<DrawArea, Error> {
+        let (start, end) = self.rotate_area(start, end);
+        self.set_draw_area(start, end)
+    }
+
+    /// Map an area given in the current orientation to display RAM addresses
+    pub(crate) fn rotate_area(&self, start: (u8, u8), end: (u8, u8)) -> DrawArea {
+        match self.display_rotation {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (start, end),
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => {
+                ((start.1, start.0), (end.1, end.0))
+            }
+        }
+    }
+
+    /// Pad the data of every `draw` to a multiple of `alignment` bytes, for parallel interfaces
+    /// that reject transfers not filling their bus width. The padding repeats the last pixel and
+    /// lands in display RAM like any other data, moving the RAM position on: draw whole rows of a
+    /// suitable width, as `clear` and `flush` do, to never need it. Only applies to
+    /// `ColorMode::Rgb565`. Defaults to 1, i.e. no padding, which is right for SPI.
+    ///
+    /// Returns `Error::OutOfBounds` for an alignment of 0 or more than 8 bytes.
+    pub fn set_data_alignment(&mut self, alignment: u8) -> Result<(), Error> {
+        if alignment == 0 || alignment as usize > MAX_DATA_ALIGNMENT {
+            return Err(Error::OutOfBounds);
+        }
+        self.data_alignment = alignment;
+        Ok(())
+    }
+
+    /// Get the alignment of data transfers, see [`set_data_alignment`](Self::set_data_alignment)
+    pub fn data_alignment(&self) -> u8 {
+        self.data_alignment
+    }
+
+    /// Enable or disable skipping the column and row commands in `set_draw_area` when the window
+    /// did not change. Enabled by default.
+    pub fn set_draw_area_caching(&mut self, enable: bool) {
+        self.draw_area_caching = enable;
+        self.invalidate_draw_area();
+    }
+
+    /// Forget the last programmed draw window, so that the next `set_draw_area` sends it in full.
+    /// Call this after anything that moves the window or the RAM position behind the back of the
+    /// driver, e.g. a hardware reset.
+    pub fn invalidate_draw_area(&mut self) {
+        self.draw_area = None;
+        self.draw_offset = 0;
+    }
+
+    /// Whether the data sent through `draw` since the draw window was programmed filled it
+    /// exactly, or a whole number of times. A `false` after drawing an area in full means that
+    /// too few or too many bytes were sent, and the following data lands at an unexpected
+    /// position unless a new window is set first. Also `false` while the window is not known.
+    pub fn window_filled(&self) -> bool {
+        self.draw_area.is_some() && self.draw_offset == 0
+    }
+
+    /// Recover from a draw window that got out of step, e.g. after a `draw` of the wrong length
+    /// or commands sent through [`interface_mut`](Self::interface_mut): forget the window the
+    /// driver believes is programmed and set the full screen window with the RAM position at its
+    /// start. With the `defmt` feature a `draw` overrunning its window logs a warning.
+    pub fn resync(&mut self) -> Result<(), Error> {
+        self.invalidate_draw_area();
+        let (display_width, display_height) = self.display_size.dimensions();
+        self.set_draw_area((0, 0), (display_width, display_height))?;
+        Ok(())
+    }
+
+    /// Get the RAM address as `(column, row)` that the next pixel sent through `draw` lands on,
+    /// following the display as it advances through the draw window and wraps at its edges. Note
+    /// that this is in display coordinates, for 90º and 270º rotations the display walks down the
+    /// columns instead of along the rows.
+    ///
+    /// Returns `None` if the position is not known, i.e. before the first `set_draw_area`, after
+    /// [`invalidate_draw_area`](Self::invalidate_draw_area) or a failed transfer.
+    pub fn current_position(&self) -> Option<(u8, u8)> {
+        let ((column_start, row_start), (column_end, row_end)) = self.draw_area?;
+        let pixel = self.draw_offset / 2;
+        let (width, height) = (
+            (column_end - column_start) as usize,
+            (row_end - row_start) as usize,
+        );
+        let (column, row) = match self.display_rotation {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (pixel % width, pixel / width),
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => {
+                (pixel / height, pixel % height)
+            }
+        };
+        Some((column_start + column as u8, row_start + row as u8))
+    }
+
+    /// Set the draw area and start streaming data into it in several steps. The returned guard
+    /// keeps track of how many bytes the window still takes, so that a renderer can produce the
+    /// content incrementally without re-programming the window in between.
+    pub fn begin_stream(
+        &mut self,
+        start: (u8, u8),
+        end: (u8, u8),
+    ) -> Result<StreamGuard<'_, DI>, Error> {
+        let (start, end) = self.set_draw_area(start, end)?;
+        let remaining = (end.0 - start.0) as usize * (end.1 - start.1) as usize * 2;
+        Ok(StreamGuard {
+            display: self,
+            remaining,
+        })
+    }
+
+    /// Issue `WriteRam` without changing the draw area, so that following `draw` calls continue
+    /// at the current position in the framebuffer of the display.
+    pub fn write_ram(&mut self) -> Result<(), Error> {
+        self.command(Command::WriteRam)?;
+        Ok(())
+    }
+
+    /// Send a NOP (`E3h`), e.g. to pad the gaps between commands for panels that need more time
+    /// on slow buses. Like any other command it ends a running `WriteRam`, data sent through
+    /// `draw` afterwards needs [`write_ram`](Self::write_ram) first.
+    pub fn send_nop(&mut self) -> Result<(), Error> {
+        self.command(Command::Nop)?;
         Ok(())
     }
 
     /// Send the data to the display for drawing at the current position in the framebuffer
     /// and advance the position accordingly. Cf. `set_draw_area` to modify the affected area by
     /// this method.
-    pub fn draw(&mut self, buffer: &[u8]) -> Result<(), DisplayError> {
-        self.iface.send_data(DataFormat::U8(buffer))?;
+    ///
+    /// In `ColorMode::Rgb666` returns `Error::InvalidData` without sending anything if `buffer`
+    /// ends in the middle of a pixel.
+    pub fn draw(&mut self, buffer: &[u8]) -> Result<(), Error> {
+        let sent = match self.config.color_mode {
+            ColorMode::Rgb565 => self.send_aligned(buffer).map_err(Error::from),
+            ColorMode::Rgb666 => self.send_rgb666(buffer).map(|()| 0),
+        };
+        let padding = match sent {
+            Ok(padding) => padding,
+            Err(err @ Error::Interface(_)) => {
+                // No telling how far the RAM position moved
+                self.invalidate_draw_area();
+                return Err(err);
+            }
+            Err(err) => return Err(err),
+        };
+        if let Some((start, end)) = self.draw_area {
+            let size = (end.0 - start.0) as usize * (end.1 - start.1) as usize * 2;
+            let written = self.draw_offset + buffer.len() + padding;
+            // Running past the end of the window anywhere but on a whole number of windows puts
+            // the following data at an unexpected position, see `resync`
+            #[cfg(feature = "defmt")]
+            if written > size && written % size != 0 {
+                defmt::warn!(
+                    "draw of {=usize} bytes overran the {=usize} byte window by {=usize}",
+                    buffer.len(),
+                    size,
+                    written % size
+                );
+            }
+            self.draw_offset = written % size;
+        }
+        Ok(())
+    }
+
+    /// Send `command`, recording the error if the interface fails
+    fn command(&mut self, command: Command) -> Result<(), DisplayError> {
+        self.commands_sent = self.commands_sent.wrapping_add(1);
+        let sent = command.send(&mut self.iface);
+        self.record(sent)
+    }
+
+    /// Send `data` as is, recording the error if the interface fails
+    fn data(&mut self, data: &[u8]) -> Result<(), DisplayError> {
+        let sent = self.iface.send_data(DataFormat::U8(data));
+        self.record(sent)
+    }
+
+    fn record(&mut self, result: Result<(), DisplayError>) -> Result<(), DisplayError> {
+        if let Err(error) = &result {
+            self.last_error = Some(error.clone());
+        }
+        result
+    }
+
+    /// Send `buffer` padded to the data alignment by repeating its last pixel, returning the
+    /// number of bytes of padding
+    fn send_aligned(&mut self, buffer: &[u8]) -> Result<usize, DisplayError> {
+        let alignment = self.data_alignment as usize;
+        let tail_len = buffer.len() % alignment;
+        if tail_len == 0 {
+            self.data(buffer)?;
+            return Ok(0);
+        }
+        let (head, tail) = buffer.split_at(buffer.len() - tail_len);
+        if !head.is_empty() {
+            self.data(head)?;
+        }
+        let mut padded = [0u8; MAX_DATA_ALIGNMENT];
+        padded[..tail_len].copy_from_slice(tail);
+        let last_pixel = &buffer[buffer.len().saturating_sub(2)..];
+        for (i, byte) in padded[tail_len..alignment].iter_mut().enumerate() {
+            *byte = last_pixel[i % last_pixel.len()];
+        }
+        self.data(&padded[..alignment])?;
+        Ok(alignment - tail_len)
+    }
+
+    /// Widen big endian RGB565 pixels to the three bytes of `ColorMode::Rgb666`, in the order of
+    /// the swapped colour sequence set up by `SetRemap`
+    fn send_rgb666(&mut self, buffer: &[u8]) -> Result<(), Error> {
+        if buffer.len() % 2 != 0 {
+            return Err(Error::InvalidData);
+        }
+        let mut chunk = [0u8; RGB666_CHUNK_PIXELS * 3];
+        for pixels in buffer.chunks(RGB666_CHUNK_PIXELS * 2) {
+            let len = pixels.len() / 2 * 3;
+            for (out, pixel) in chunk.chunks_exact_mut(3).zip(pixels.chunks_exact(2)) {
+                let color = u16::from_be_bytes([pixel[0], pixel[1]]);
+                let r = (color >> 11) as u8 & 0x1F;
+                let g = (color >> 5) as u8 & 0x3F;
+                let b = color as u8 & 0x1F;
+                out.copy_from_slice(&[r << 1 | r >> 4, g, b << 1 | b >> 4]);
+            }
+            self.data(&chunk[..len])?;
+        }
         Ok(())
     }
 
+    /// Estimate the frame period in nanoseconds for an oscillator running at `fosc_hz`, following
+    /// `F_frm = F_osc / (D * K * mux)` from the datasheet, where `D` is the clock divider, `K` the
+    /// number of display clocks per row and `mux` the number of rows driven. The datasheet does not
+    /// specify the oscillator frequency, so it has to be measured or estimated for the panel.
+    pub fn frame_period_ns(&self, fosc_hz: u32) -> u32 {
+        let divider = 1u64 << (self.config.clock_div & 0x0F);
+        let phase1 = (PHASE_LENGTH & 0x0F) as u64 * 2 + 1;
+        let phase2 = (PHASE_LENGTH >> 4) as u64;
+        let clocks_per_row = phase1 + phase2 + CURRENT_DRIVE_DCLKS;
+
+        let period = 1_000_000_000 * divider * clocks_per_row * self.active_rows() as u64
+            / fosc_hz.max(1) as u64;
+        period.min(u32::MAX as u64) as u32
+    }
+
+    /// Set whether the reset line is active low (the default) or active high
+    pub fn set_reset_active_low(&mut self, reset_active_low: bool) {
+        self.reset_active_low = reset_active_low;
+    }
+
+    /// Check whether the reset line is active low
+    pub fn reset_active_low(&self) -> bool {
+        self.reset_active_low
+    }
+
     /// Get the configured display size
     pub fn get_size(&self) -> DisplaySize {
         self.display_size
@@ -108,11 +1023,14 @@ where
     /// Get display dimensions, taking into account the current rotation of the display
     ///
     /// ```rust
+    /// # use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+    /// # use ssd1351::display::Display;
+    /// # use ssd1351::properties::{DisplayRotation, DisplaySize};
     /// # struct FakeInterface;
     /// #
-    /// # impl DisplayInterface for FakeInterface {
-    /// #     fn send_command(&mut self, cmd: u8) -> Result<(), ()> { Ok(()) }
-    /// #     fn send_data(&mut self, buf: &[u8]) -> Result<(), ()> { Ok(()) }
+    /// # impl WriteOnlyDataCommand for FakeInterface {
+    /// #     fn send_commands(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> { Ok(()) }
+    /// #     fn send_data(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> { Ok(()) }
     /// # }
     /// #
     /// # let interface = FakeInterface {};
@@ -141,30 +1059,876 @@ where
         }
     }
 
+    /// Number of bytes sent for a full frame, i.e. the [`active_rows`](Self::active_rows) of the
+    /// display. This is two bytes per pixel in `ColorMode::Rgb565` and three in
+    /// `ColorMode::Rgb666`, regardless of the framebuffer format. Divide the SPI clock by eight
+    /// times this to get an upper bound on the frame rate.
+    pub fn frame_size_bytes(&self) -> usize {
+        let bytes_per_pixel = match self.config.color_mode {
+            ColorMode::Rgb565 => 2,
+            ColorMode::Rgb666 => 3,
+        };
+        self.frame_pixels() * bytes_per_pixel
+    }
+
+    /// Number of pixels in a full frame of the active rows
+    pub(crate) fn frame_pixels(&self) -> usize {
+        self.display_size.dimensions().0 as usize * self.active_rows() as usize
+    }
+
+    /// Optional controller features supported by the panel, see [`DisplaySize::capabilities`]
+    pub fn capabilities(&self) -> Capabilities {
+        self.display_size.capabilities()
+    }
+
+    /// Get the visible extent of the display, taking into account the current rotation and the
+    /// rows left dark by [`set_active_rows`](Self::set_active_rows)
+    pub fn display_bounds(&self) -> Region {
+        let (width, height) = match self.display_rotation {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => {
+                (self.display_size.dimensions().0, self.active_rows())
+            }
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => {
+                (self.active_rows(), self.display_size.dimensions().0)
+            }
+        };
+        Region {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        }
+    }
+
     /// Get the display rotation
     pub fn get_rotation(&self) -> DisplayRotation {
         self.display_rotation
     }
 
     /// Set the display rotation
-    pub fn set_rotation(&mut self, display_rotation: DisplayRotation) -> Result<(), DisplayError> {
+    pub fn set_rotation(&mut self, display_rotation: DisplayRotation) -> Result<(), Error> {
         self.display_rotation = display_rotation;
+        // The address increment direction changes with the rotation
+        self.invalidate_draw_area();
+        let color_mode = self.config.color_mode;
 
-        match display_rotation {
-            DisplayRotation::Rotate0 => {
-                Command::SetRemap(false, false, true).send(&mut self.iface)?;
-            }
-            DisplayRotation::Rotate90 => {
-                Command::SetRemap(true, true, true).send(&mut self.iface)?;
+        self.command(Command::SetRemap(
+            remap_byte(display_rotation, false, false, false),
+            color_mode,
+        ))?;
+        self.command(Command::StartLine(self.start_line()))?;
+
+        Ok(())
+    }
+
+    /// The display start line that puts RAM rows `0..active_rows` on the driven COM lines. With
+    /// a reversed COM scan (0º and 90º) the driven lines show the RAM rows counting down from the
+    /// top of the RAM, so the start line has to move them by the number of driven rows. On a full
+    /// 128 row panel this wraps around to 0, a 128x96 panel or fewer active rows would show the
+    /// bottom of the RAM instead of what was drawn.
+    fn start_line(&self) -> u8 {
+        match self.display_rotation {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate90 => self.active_rows() % 128,
+            DisplayRotation::Rotate180 | DisplayRotation::Rotate270 => 0,
+        }
+    }
+}
+
+impl<DI> Display<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Run one step of `init`. `feed` is called after every chunk of the clear and at the end of
+    /// the other steps.
+    fn init_phase(
+        &mut self,
+        phase: InitPhase,
+        mut feed: Option<&mut dyn FnMut()>,
+    ) -> Result<(), Error> {
+        match phase {
+            InitPhase::Unlock => {
+                self.unlock()?;
+                self.command(Command::DisplayOn(false))?;
             }
-            DisplayRotation::Rotate180 => {
-                Command::SetRemap(false, true, false).send(&mut self.iface)?;
+            InitPhase::Config => self.send_config()?,
+            InitPhase::Clear => {
+                return self.clear_with(0x0000, || {
+                    if let Some(feed) = feed.as_deref_mut() {
+                        feed();
+                    }
+                })
             }
-            DisplayRotation::Rotate270 => {
-                Command::SetRemap(true, false, false).send(&mut self.iface)?;
+            InitPhase::DisplayOn => self.command(Command::DisplayOn(true))?,
+        }
+        if let Some(feed) = feed {
+            feed();
+        }
+        Ok(())
+    }
+
+    /// The steps of `init`, each one timed and counted separately
+    fn init_phases<F>(&mut self, now_us: &mut F) -> Result<InitStats, Error>
+    where
+        F: FnMut() -> u32,
+    {
+        let mut stats = InitStats::default();
+        for phase in InitPhase::ALL {
+            let phase_stats = self.phase(now_us, |display| display.init_phase(phase, None))?;
+            match phase {
+                InitPhase::Unlock => stats.unlock = phase_stats,
+                InitPhase::Config => stats.config = phase_stats,
+                InitPhase::Clear => stats.clear = phase_stats,
+                InitPhase::DisplayOn => stats.display_on = phase_stats,
             }
-        };
+        }
+        Ok(stats)
+    }
+
+    fn phase<F, P>(&mut self, now_us: &mut F, run: P) -> Result<PhaseStats, Error>
+    where
+        F: FnMut() -> u32,
+        P: FnOnce(&mut Self) -> Result<(), Error>,
+    {
+        let start = now_us();
+        let commands = self.commands_sent;
+        run(self)?;
+        Ok(PhaseStats {
+            commands: self.commands_sent.wrapping_sub(commands),
+            elapsed_us: now_us().wrapping_sub(start),
+        })
+    }
+}
+
+/// Draw window being filled with data in several steps, see [`Display::begin_stream`]
+pub struct StreamGuard<'a, DI> {
+    display: &'a mut Display<DI>,
+    remaining: usize,
+}
 
+impl<DI> StreamGuard<'_, DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Send the next part of the data. Returns `Error::OutOfBounds` without sending
+    /// anything if `data` holds more bytes than the window has left.
+    pub fn push(&mut self, data: &[u8]) -> Result<(), Error> {
+        if data.len() > self.remaining {
+            return Err(Error::OutOfBounds);
+        }
+        self.display.draw(data)?;
+        self.remaining -= data.len();
         Ok(())
     }
+
+    /// Get the number of bytes still needed to fill the window
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Finish the stream. Returns `Error::InvalidData` if the window has not been
+    /// filled completely, the display RAM position is then left in the middle of the window.
+    pub fn end_stream(self) -> Result<(), Error> {
+        if self.remaining != 0 {
+            return Err(Error::InvalidData);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{self, Transfer};
+
+    #[test]
+    fn stream_pushes_share_one_window() {
+        let (mut display, mock) =
+            mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+
+        let mut stream = display.begin_stream((2, 3), (6, 5)).unwrap();
+        assert_eq!(stream.remaining(), 16);
+        stream.push(&[1; 6]).unwrap();
+        stream.push(&[2; 6]).unwrap();
+        assert!(matches!(stream.push(&[0; 6]), Err(Error::OutOfBounds)));
+        stream.push(&[3; 4]).unwrap();
+        assert_eq!(stream.remaining(), 0);
+        stream.end_stream().unwrap();
+
+        let transfers = mock.take();
+        assert_eq!(mock::commands(&transfers), [0x15, 0x75, 0x5C]);
+        assert_eq!(
+            transfers[transfers.len() - 3..],
+            [
+                Transfer::Data(vec![1; 6]),
+                Transfer::Data(vec![2; 6]),
+                Transfer::Data(vec![3; 4]),
+            ]
+        );
+    }
+
+    #[test]
+    fn stream_left_unfilled_is_invalid() {
+        let (mut display, _) = mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+
+        let mut stream = display.begin_stream((0, 0), (2, 2)).unwrap();
+        stream.push(&[0; 6]).unwrap();
+        assert!(matches!(stream.end_stream(), Err(Error::InvalidData)));
+    }
+
+    #[test]
+    fn window_cache_skips_a_repeated_window() {
+        let (mut display, mock) =
+            mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let window = ((4, 4), (8, 6));
+
+        display.set_draw_area(window.0, window.1).unwrap();
+        display.draw(&[0; 16]).unwrap();
+        mock.take();
+        display.set_draw_area(window.0, window.1).unwrap();
+        assert_eq!(mock::commands(&mock.take()), [0x5C]);
+
+        // Half filled, the RAM position is not back at the start of the window
+        display.draw(&[0; 8]).unwrap();
+        display.set_draw_area(window.0, window.1).unwrap();
+        assert_eq!(mock::commands(&mock.take()), [0x15, 0x75, 0x5C]);
+
+        display.set_draw_area_caching(false);
+        display.set_draw_area(window.0, window.1).unwrap();
+        display.draw(&[0; 16]).unwrap();
+        display.set_draw_area(window.0, window.1).unwrap();
+        assert_eq!(
+            mock::commands(&mock.take()),
+            [0x15, 0x75, 0x5C, 0x15, 0x75, 0x5C]
+        );
+    }
+
+    #[test]
+    fn resync_and_rotation_invalidate_the_window_cache() {
+        let (mut display, mock) =
+            mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let full = ((0, 0), (128, 128));
+
+        display.set_draw_area(full.0, full.1).unwrap();
+        mock.take();
+        display.resync().unwrap();
+        assert_eq!(mock::commands(&mock.take()), [0x15, 0x75, 0x5C]);
+        assert!(display.window_filled());
+
+        display.set_rotation(DisplayRotation::Rotate90).unwrap();
+        assert!(!display.window_filled());
+        mock.take();
+        display.set_draw_area(full.0, full.1).unwrap();
+        assert_eq!(mock::commands(&mock.take()), [0x15, 0x75, 0x5C]);
+    }
+
+    #[test]
+    fn reinit_config_keeps_the_image() {
+        let (mut display, mock) =
+            mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+
+        display.reinit_config().unwrap();
+        let commands = mock::commands(&mock.take());
+        assert!(!commands.contains(&0x5C));
+        assert!(!commands.contains(&0xAE));
+        assert_eq!(commands.last(), Some(&0xAF));
+
+        display.init().unwrap();
+        let transfers = mock.take();
+        assert!(mock::commands(&transfers).contains(&0xAE));
+        assert_eq!(mock::ram_data(&transfers).len(), 128 * 128 * 2);
+    }
+
+    #[test]
+    fn current_position_follows_the_ram_address() {
+        let (mut display, mock) =
+            mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        assert_eq!(display.current_position(), None);
+
+        display.set_draw_area((10, 20), (14, 22)).unwrap();
+        assert_eq!(display.current_position(), Some((10, 20)));
+        display.draw(&[0; 6]).unwrap();
+        assert_eq!(display.current_position(), Some((13, 20)));
+        display.draw(&[0; 4]).unwrap();
+        assert_eq!(display.current_position(), Some((11, 21)));
+        display.draw(&[0; 6]).unwrap();
+        assert_eq!(display.current_position(), Some((10, 20)));
+
+        display.set_rotation(DisplayRotation::Rotate90).unwrap();
+        assert_eq!(display.current_position(), None);
+        display.set_draw_area((10, 20), (14, 22)).unwrap();
+        display.draw(&[0; 6]).unwrap();
+        assert_eq!(display.current_position(), Some((11, 21)));
+
+        mock.set_fail(true);
+        assert!(display.set_draw_area((0, 0), (1, 1)).is_err());
+        assert_eq!(display.current_position(), None);
+    }
+
+    #[test]
+    fn frame_size_bytes_follows_the_active_rows() {
+        let (mut display, _) = mock::display(DisplaySize::Display128x96, DisplayRotation::Rotate90);
+        assert_eq!(display.frame_size_bytes(), 128 * 96 * 2);
+
+        display.set_active_rows(40).unwrap();
+        assert_eq!(display.frame_size_bytes(), 128 * 40 * 2);
+        let bounds = display.display_bounds();
+        assert_eq!(
+            display.frame_size_bytes(),
+            bounds.width as usize * bounds.height as usize * 2
+        );
+    }
+
+    #[test]
+    fn active_rows_set_the_mux_ratio_and_clamp_windows() {
+        let (mut display, mock) =
+            mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        display.set_active_rows(40).unwrap();
+        assert_eq!(mock::args(&mock.take(), 0xCA), [[39]]);
+        display.init().unwrap();
+        let transfers = mock.take();
+        assert_eq!(mock::args(&transfers, 0xCA), [[39]]);
+        // The clear only covers the driven rows
+        assert_eq!(mock::args(&transfers, 0x75), [[0, 39]]);
+        assert_eq!(mock::ram_data(&transfers).len(), 128 * 40 * 2);
+
+        let area = display.set_draw_area((0, 30), (128, 128)).unwrap();
+        assert_eq!(area, ((0, 30), (128, 40)));
+        assert_eq!(mock::args(&mock.take(), 0x75), [[30, 39]]);
+
+        display.set_active_rows(128).unwrap();
+        assert_eq!(mock::args(&mock.take(), 0xCA), [[127]]);
+        assert_eq!(
+            display.set_draw_area((0, 30), (128, 128)).unwrap().1,
+            (128, 128)
+        );
+    }
+
+    #[test]
+    fn rgb666_frames_take_three_bytes_per_pixel() {
+        let (mut display, mock) =
+            mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        display.set_color_mode(ColorMode::Rgb666).unwrap();
+        assert_eq!(display.frame_size_bytes(), 128 * 128 * 3);
+
+        display.set_draw_area((0, 0), (2, 1)).unwrap();
+        mock.take();
+        assert!(matches!(
+            display.draw(&[0xF8, 0x00, 0x07]),
+            Err(Error::InvalidData)
+        ));
+        assert_eq!(mock.take(), []);
+        assert!(display.window_filled());
+
+        display.draw(&[0xF8, 0x00, 0x00, 0x1F]).unwrap();
+        assert_eq!(mock.take(), [Transfer::Data(vec![0x3F, 0, 0, 0, 0, 0x3F])]);
+    }
+
+    #[test]
+    fn init_instrumented_counts_the_commands_of_init() {
+        let (mut display, mock) =
+            mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let mut now = 0;
+
+        let stats = display
+            .init_instrumented(|| {
+                now += 10;
+                now
+            })
+            .unwrap();
+        let transfers = mock.take();
+        let commands = transfers
+            .iter()
+            .filter(|transfer| matches!(transfer, Transfer::Command(_)))
+            .count();
+        assert_eq!(stats.commands() as usize, commands);
+        assert_eq!(stats.clear.commands, 3);
+        assert_eq!(stats.elapsed_us(), 4 * 10);
+        assert_eq!(mock::ram_data(&transfers).len(), 128 * 128 * 2);
+        assert!(display.window_filled());
+
+        display.init().unwrap();
+        assert_eq!(mock.take(), transfers);
+    }
+
+    #[test]
+    fn resync_recovers_from_a_short_draw() {
+        let (mut display, mock) =
+            mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let full = ((0, 0), (128, 128));
+
+        display.set_draw_area(full.0, full.1).unwrap();
+        display.draw(&[0; 128 * 128 * 2 - 2]).unwrap();
+        assert!(!display.window_filled());
+        assert_eq!(display.current_position(), Some((127, 127)));
+
+        display.resync().unwrap();
+        assert!(display.window_filled());
+        assert_eq!(display.current_position(), Some((0, 0)));
+        mock.take();
+        display.draw(&[0; 128 * 128 * 2]).unwrap();
+        display.set_draw_area(full.0, full.1).unwrap();
+        assert_eq!(mock::commands(&mock.take()), [0x5C]);
+    }
+
+    #[test]
+    fn init_chunked_sends_what_init_sends() {
+        let (mut display, mock) =
+            mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        display.init().unwrap();
+        let init = mock.take();
+
+        let mut feeds = 0;
+        display.init_chunked(|| feeds += 1).unwrap();
+        assert_eq!(mock.take(), init);
+        // After unlocking, the configuration, every chunk of the clear and turning it on
+        assert_eq!(feeds, 3 + 128 * 128 * 2 / CLEAR_CHUNK_SIZE);
+    }
+
+    #[test]
+    fn last_error_is_recorded_kept_and_cleared() {
+        let (mut display, mock) =
+            mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        display.set_master_contrast(0x0F).unwrap();
+        assert!(display.last_error().is_none());
+
+        mock.set_fail(true);
+        assert!(matches!(
+            display.set_master_contrast(0x08),
+            Err(Error::Interface(DisplayError::BusWriteError))
+        ));
+        assert!(matches!(
+            display.last_error(),
+            Some(DisplayError::BusWriteError)
+        ));
+
+        // Later transfers going through do not hide it
+        mock.set_fail(false);
+        display.set_master_contrast(0x08).unwrap();
+        display.draw(&[0; 2]).unwrap();
+        assert!(matches!(
+            display.last_error(),
+            Some(DisplayError::BusWriteError)
+        ));
+
+        display.clear_last_error();
+        assert!(display.last_error().is_none());
+        display.set_master_contrast(0x0F).unwrap();
+        assert!(display.last_error().is_none());
+    }
+
+    #[test]
+    fn parallel_bus_sees_the_bytes_of_the_spi_bus() {
+        let (mut spi, mock) = mock::display(DisplaySize::Display128x96, DisplayRotation::Rotate0);
+        let bus = mock::MockParallelBus::default();
+        let mut parallel = Display::new(
+            bus.clone(),
+            DisplaySize::Display128x96,
+            DisplayRotation::Rotate0,
+        );
+
+        let colors = [0xF800u16, 0x07E0, 0x001F, 0xFFFF];
+        let pixels: Vec<_> = colors
+            .iter()
+            .flat_map(|color| color.to_be_bytes())
+            .collect();
+        spi.init().unwrap();
+        spi.set_draw_area((4, 4), (5, 5)).unwrap();
+        spi.draw(&pixels).unwrap();
+        parallel.init().unwrap();
+        parallel.set_draw_area((4, 4), (5, 5)).unwrap();
+        parallel.draw(&pixels).unwrap();
+
+        // Every command byte is clocked with DC low and every parameter or pixel byte with DC
+        // high, in the order the SPI bus sends them
+        let expected: Vec<_> = mock
+            .take()
+            .into_iter()
+            .flat_map(|transfer| {
+                let (dc, bytes) = match transfer {
+                    Transfer::Command(bytes) => (false, bytes),
+                    Transfer::Data(bytes) => (true, bytes),
+                };
+                bytes.into_iter().map(move |byte| (dc, byte))
+            })
+            .collect();
+        let cycles = bus.take();
+        assert_eq!(cycles, expected);
+
+        // Pixel data goes out high byte first, the same as 16 bit words on the 8 bit bus
+        let mut words = colors.iter().copied();
+        let mut words_bus = bus.clone();
+        words_bus
+            .send_data(DataFormat::U16BEIter(&mut words))
+            .unwrap();
+        assert_eq!(bus.take(), cycles[cycles.len() - 8..]);
+    }
+
+    #[test]
+    fn start_line_keeps_the_drawn_rows_on_a_128x96_panel() {
+        let rotations = [
+            (DisplayRotation::Rotate0, 96),
+            (DisplayRotation::Rotate90, 96),
+            (DisplayRotation::Rotate180, 0),
+            (DisplayRotation::Rotate270, 0),
+        ];
+        for (rotation, start_line) in rotations {
+            let (mut display, mock) = mock::display(DisplaySize::Display128x96, rotation);
+            display.init().unwrap();
+            assert_eq!(mock::args(&mock.take(), 0xA1), [[start_line]]);
+            display.set_rotation(rotation).unwrap();
+            assert_eq!(mock::args(&mock.take(), 0xA1), [[start_line]]);
+
+            // The whole screen is drawn into RAM rows 0 to 95, the ones the start line puts on
+            // the driven COM lines
+            let bounds = display.display_bounds();
+            display
+                .set_draw_area_logical((0, 0), (bounds.width, bounds.height))
+                .unwrap();
+            let transfers = mock.take();
+            assert_eq!(mock::args(&transfers, 0x15), [[0, 127]]);
+            assert_eq!(mock::args(&transfers, 0x75), [[0, 95]]);
+        }
+    }
+
+    #[test]
+    fn logical_areas_are_rotated_into_ram_addresses() {
+        let rotations = [
+            (DisplayRotation::Rotate0, ((5, 10), (25, 14))),
+            (DisplayRotation::Rotate90, ((10, 5), (14, 25))),
+            (DisplayRotation::Rotate180, ((5, 10), (25, 14))),
+            (DisplayRotation::Rotate270, ((10, 5), (14, 25))),
+        ];
+        for (rotation, ram_area) in rotations {
+            let (mut display, mock) = mock::display(DisplaySize::Display128x128, rotation);
+            assert_eq!(display.rotate_area((5, 10), (25, 14)), ram_area);
+            let area = display.set_draw_area_logical((5, 10), (25, 14)).unwrap();
+            assert_eq!(area, ram_area);
+            let ((column_start, row_start), (column_end, row_end)) = ram_area;
+            let transfers = mock.take();
+            assert_eq!(
+                mock::args(&transfers, 0x15),
+                [[column_start, column_end - 1]]
+            );
+            assert_eq!(mock::args(&transfers, 0x75), [[row_start, row_end - 1]]);
+        }
+    }
+
+    #[test]
+    fn frame_size_follows_the_size_and_color_mode() {
+        let frames = [
+            (
+                DisplaySize::Display128x128,
+                ColorMode::Rgb565,
+                128 * 128 * 2,
+            ),
+            (
+                DisplaySize::Display128x128,
+                ColorMode::Rgb666,
+                128 * 128 * 3,
+            ),
+            (DisplaySize::Display128x96, ColorMode::Rgb565, 128 * 96 * 2),
+            (DisplaySize::Display128x96, ColorMode::Rgb666, 128 * 96 * 3),
+        ];
+        for (size, color_mode, bytes) in frames {
+            let (mut display, _) = mock::display(size, DisplayRotation::Rotate0);
+            display.set_color_mode(color_mode).unwrap();
+            assert_eq!(display.frame_size_bytes(), bytes);
+        }
+    }
+
+    #[test]
+    fn gpio_modes_are_packed_into_one_byte() {
+        let (mut display, mock) =
+            mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let pins = [
+            (GpioMode::InputDisabled, GpioMode::InputDisabled, 0b0000),
+            (GpioMode::OutputHigh, GpioMode::InputEnabled, 0b0111),
+            (GpioMode::InputEnabled, GpioMode::OutputLow, 0b1001),
+        ];
+        for (pin0, pin1, bits) in pins {
+            display.set_gpio(pin0, pin1).unwrap();
+            assert_eq!(mock::args(&mock.take(), 0xB5), [[bits]]);
+            assert_eq!(display.config().gpio, (pin0, pin1));
+        }
+    }
+
+    #[test]
+    fn gamma_tables_are_checked_before_they_are_sent() {
+        let (mut display, mock) =
+            mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let table: GammaTable = core::array::from_fn(|i| i as u8 * 2 + 2);
+        display.set_gamma(&table).unwrap();
+        assert_eq!(
+            mock.take(),
+            [
+                Transfer::Command(vec![0xB8]),
+                Transfer::Data(table.to_vec()),
+            ]
+        );
+
+        let mut falling = table;
+        falling.swap(20, 21);
+        let mut too_long = table;
+        too_long[62] = 181;
+        for bad in [falling, too_long] {
+            assert!(matches!(display.set_gamma(&bad), Err(Error::InvalidData)));
+            assert_eq!(mock.take(), []);
+            assert_eq!(display.config().gamma, Some(table));
+        }
+
+        display.set_linear_gamma().unwrap();
+        assert_eq!(mock.take(), [Transfer::Command(vec![0xB9])]);
+        assert_eq!(display.config().gamma, None);
+    }
+
+    #[test]
+    fn nop_is_a_lone_command_byte() {
+        let (mut display, mock) =
+            mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        display.send_nop().unwrap();
+        display.send_nop().unwrap();
+        assert_eq!(
+            mock.take(),
+            [Transfer::Command(vec![0xE3]), Transfer::Command(vec![0xE3])]
+        );
+    }
+
+    #[test]
+    fn precharge_voltage_is_sent_up_to_1fh() {
+        let (mut display, mock) =
+            mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        for level in [0x00, 0x17, 0x1F] {
+            display.set_precharge_voltage_level(level).unwrap();
+            assert_eq!(mock::args(&mock.take(), 0xBB), [[level]]);
+        }
+        for level in [0x20, 0xFF] {
+            assert!(matches!(
+                display.set_precharge_voltage_level(level),
+                Err(Error::OutOfBounds)
+            ));
+            assert_eq!(mock.take(), []);
+        }
+        assert_eq!(display.config().precharge_voltage, 0x1F);
+    }
+
+    #[test]
+    fn second_precharge_is_sent_from_1_to_15() {
+        let (mut display, mock) =
+            mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        for period in [1, 8, 15] {
+            display.set_second_precharge(period).unwrap();
+            assert_eq!(
+                mock.take(),
+                [Transfer::Command(vec![0xB6]), Transfer::Data(vec![period])]
+            );
+        }
+        for period in [0, 16] {
+            assert!(matches!(
+                display.set_second_precharge(period),
+                Err(Error::OutOfBounds)
+            ));
+            assert_eq!(mock.take(), []);
+        }
+        assert_eq!(display.config().second_precharge, 15);
+    }
+
+    #[test]
+    fn clock_divider_sets_the_frame_period() {
+        let (mut display, mock) =
+            mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        display.init().unwrap();
+        assert_eq!(mock::args(&mock.take(), 0xB3), [[0xF1]]);
+        // 2 x (5 + 3 + 134) DCLKs for each of the 128 rows at 10 MHz
+        assert_eq!(display.frame_period_ns(10_000_000), 3_635_200);
+
+        display.optimize_for_low_brightness().unwrap();
+        assert_eq!(mock::args(&mock.take(), 0xB3), [[0xF0]]);
+        assert_eq!(display.frame_period_ns(10_000_000), 1_817_600);
+
+        display.optimize_for_normal().unwrap();
+        assert_eq!(mock::args(&mock.take(), 0xB3), [[0xF1]]);
+        assert_eq!(display.frame_period_ns(10_000_000), 3_635_200);
+    }
+
+    #[test]
+    fn command_lock_modes_send_their_bytes() {
+        let (mut display, mock) =
+            mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let modes = [
+            (CommandLockMode::Unlock, 0x12),
+            (CommandLockMode::Lock, 0x16),
+            (CommandLockMode::EnableProtected, 0xB1),
+            (CommandLockMode::DisableProtected, 0xB0),
+        ];
+        for (mode, byte) in modes {
+            display.set_command_lock(mode).unwrap();
+            assert_eq!(mock::args(&mock.take(), 0xFD), [[byte]]);
+        }
+    }
+
+    #[test]
+    fn profiles_send_contrast_and_precharge_groups() {
+        let profiles = [
+            (DisplayProfile::Vivid, [0x0F, 0x1F, 0x08]),
+            (DisplayProfile::Natural, [0x0F, 0x17, 0x01]),
+            (DisplayProfile::PowerSaver, [0x07, 0x08, 0x01]),
+            (DisplayProfile::HighContrast, [0x0F, 0x08, 0x0F]),
+        ];
+        for (profile, [contrast, voltage, period]) in profiles {
+            let (mut display, mock) =
+                mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+            display.init().unwrap();
+            mock.take();
+
+            // `init` has already unlocked the protected `BBh`, no `FDh` is needed here
+            display.apply_profile(profile).unwrap();
+            let transfers = mock.take();
+            assert_eq!(mock::commands(&transfers), [0xC7, 0xBB, 0xB6]);
+            assert_eq!(mock::args(&transfers, 0xC7), [[contrast]]);
+            assert_eq!(mock::args(&transfers, 0xBB), [[voltage]]);
+            assert_eq!(mock::args(&transfers, 0xB6), [[period]]);
+            assert_eq!(display.profile(), Some(profile));
+
+            // The profile survives `init`
+            display.init().unwrap();
+            let transfers = mock.take();
+            assert_eq!(mock::args(&transfers, 0xC7), [[contrast]]);
+            assert_eq!(mock::args(&transfers, 0xBB), [[voltage]]);
+            assert_eq!(mock::args(&transfers, 0xB6), [[period]]);
+        }
+    }
+
+    #[test]
+    fn applied_config_follows_the_setters_and_reinit() {
+        let (mut display, mock) =
+            mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        display.set_master_contrast(0x0A).unwrap();
+        display
+            .set_gpio(GpioMode::OutputHigh, GpioMode::OutputLow)
+            .unwrap();
+        display.optimize_for_low_brightness().unwrap();
+        assert_eq!(display.applied_config(), display.config());
+        assert_eq!(display.applied_config().master_contrast, 0x0A);
+        assert_eq!(display.applied_config().clock_div, 0xF0);
+
+        // A setting that fails to go out is only in the configuration
+        mock.set_fail(true);
+        assert!(display.set_second_precharge(0x0C).is_err());
+        mock.set_fail(false);
+        assert_eq!(display.config().second_precharge, 0x0C);
+        assert_ne!(display.applied_config().second_precharge, 0x0C);
+
+        mock.take();
+        display.reinit_config().unwrap();
+        let transfers = mock.take();
+        assert_eq!(mock::args(&transfers, 0xB6), [[0x0C]]);
+        assert_eq!(mock::args(&transfers, 0xC7), [[0x0A]]);
+        assert_eq!(mock::args(&transfers, 0xB3), [[0xF0]]);
+        assert_eq!(mock::args(&transfers, 0xB5), [[0b1011]]);
+        assert_eq!(display.applied_config(), display.config());
+
+        display.init().unwrap();
+        assert_eq!(display.applied_config().master_contrast, 0x0A);
+        assert_eq!(display.applied_config().second_precharge, 0x0C);
+    }
+
+    #[test]
+    fn fade_steps_the_master_contrast() {
+        let (mut display, mock) =
+            mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let mut delay = mock::MockDelay::default();
+        display.fade_to(3, 4, &mut delay, 10).unwrap();
+        assert_eq!(mock::args(&mock.take(), 0xC7), [[12], [9], [6], [3]]);
+        assert_eq!(delay.total_ns, 4 * 10_000_000);
+
+        // Steps that don't reach the next level only wait
+        let mut delay = mock::MockDelay::default();
+        display.fade_to(5, 4, &mut delay, 10).unwrap();
+        assert_eq!(mock::args(&mock.take(), 0xC7), [[4], [5]]);
+        assert_eq!(delay.total_ns, 4 * 10_000_000);
+
+        let mut delay = mock::MockDelay::default();
+        assert!(matches!(
+            display.fade_to(16, 4, &mut delay, 10),
+            Err(Error::OutOfBounds)
+        ));
+        assert_eq!(mock.take(), []);
+        assert_eq!(delay.total_ns, 0);
+    }
+
+    #[test]
+    fn data_alignment_pads_with_the_last_pixel() {
+        let (mut display, mock) =
+            mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        assert_eq!(display.data_alignment(), 1);
+        assert!(matches!(
+            display.set_data_alignment(0),
+            Err(Error::OutOfBounds)
+        ));
+        assert!(matches!(
+            display.set_data_alignment(9),
+            Err(Error::OutOfBounds)
+        ));
+        display.set_data_alignment(4).unwrap();
+        // A window of 4 pixels, 8 bytes
+        let window = ((0, 0), (4, 1));
+
+        // Three pixels go out as 8 bytes, filling the window
+        display.set_draw_area(window.0, window.1).unwrap();
+        display.draw(&[1, 2, 3, 4, 5, 6]).unwrap();
+        let transfers = mock.take();
+        assert_eq!(mock::ram_writes(&transfers), [[1, 2, 3, 4], [5, 6, 5, 6]]);
+
+        // The window is cached as the RAM position is back at its start. One pixel then takes up
+        // two in RAM, leaving it half used.
+        display.set_draw_area(window.0, window.1).unwrap();
+        display.draw(&[7, 8]).unwrap();
+        let transfers = mock.take();
+        assert_eq!(mock::commands(&transfers), [0x5C]);
+        assert_eq!(mock::ram_data(&transfers), [7, 8, 7, 8]);
+
+        // Aligned data is sent as it is, after the window is programmed again
+        display.set_draw_area(window.0, window.1).unwrap();
+        display.draw(&[0; 8]).unwrap();
+        let transfers = mock.take();
+        assert_eq!(mock::commands(&transfers), [0x15, 0x75, 0x5C]);
+        assert_eq!(mock::ram_writes(&transfers), [[0; 8]]);
+
+        display.set_data_alignment(1).unwrap();
+        display.set_draw_area(window.0, window.1).unwrap();
+        display.draw(&[1, 2, 3, 4, 5, 6]).unwrap();
+        assert_eq!(mock::ram_writes(&mock.take()), [[1, 2, 3, 4, 5, 6]]);
+    }
+
+    #[test]
+    fn set_draw_area_returns_the_clamped_window() {
+        let (mut display, mock) =
+            mock::display(DisplaySize::Display128x96, DisplayRotation::Rotate0);
+        let cases = [
+            // Inside the display the window is taken as it is
+            (((4, 8), (20, 16)), ((4, 8), (20, 16))),
+            // The end is clamped to the display
+            (((100, 90), (200, 200)), ((100, 90), (128, 96))),
+            // So is a start beyond it, leaving the last pixel
+            (((130, 100), (140, 120)), ((127, 95), (128, 96))),
+            // An empty window still covers one pixel
+            (((10, 10), (10, 10)), ((9, 9), (10, 10))),
+        ];
+        for (requested, expected) in cases {
+            let window = display.set_draw_area(requested.0, requested.1).unwrap();
+            assert_eq!(window, expected);
+            let ((x0, y0), (x1, y1)) = window;
+            // Exactly the bytes of the returned window fill it
+            let len = (x1 - x0) as usize * (y1 - y0) as usize * 2;
+            display.draw(&vec![0; len]).unwrap();
+            let transfers = mock.take();
+            assert_eq!(mock::args(&transfers, 0x15), [[x0, x1 - 1]]);
+            assert_eq!(mock::args(&transfers, 0x75), [[y0, y1 - 1]]);
+            assert_eq!(mock::ram_data(&transfers).len(), len);
+
+            // So the RAM position is back at the start and repeating the window is cached
+            display.set_draw_area(requested.0, requested.1).unwrap();
+            assert_eq!(mock::commands(&mock.take()), [0x5C]);
+        }
+    }
 }
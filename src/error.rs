@@ -0,0 +1,81 @@
+//! Errors returned by the driver
+
+use display_interface::DisplayError;
+
+/// Errors returned by the driver, `E` is the error of the interface
+#[derive(Clone, Debug)]
+pub enum Error<E = DisplayError> {
+    /// The interface failed to send a command or data
+    Interface(E),
+    /// There is no framebuffer of the right size for the display, a scratch buffer is too small
+    /// or a snapshot does not match the framebuffer
+    BufferSize,
+    /// Data handed to the driver is malformed or does not match the area it covers
+    InvalidData,
+    /// A position, area or setting is out of the range the display supports
+    OutOfBounds,
+    /// The panel lacks the capability needed, see
+    /// [`DisplaySize::capabilities`](crate::properties::DisplaySize::capabilities)
+    Unsupported,
+}
+
+impl<E> From<E> for Error<E> {
+    fn from(error: E) -> Self {
+        Error::Interface(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::GammaTable;
+    use crate::mock;
+    use crate::properties::{ColorMode, DisplayRotation, DisplaySize, GpioMode};
+
+    #[test]
+    fn public_calls_return_every_error() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+
+        #[cfg(feature = "buffered")]
+        {
+            display.take_buffer().unwrap();
+            assert!(matches!(display.flush(), Err(Error::BufferSize)));
+        }
+        #[cfg(all(feature = "graphics", not(feature = "buffered")))]
+        {
+            use embedded_graphics_core::pixelcolor::{Rgb565, RgbColor};
+            use embedded_graphics_core::primitives::Rectangle;
+            let area = Rectangle::new(Default::default(), Default::default());
+            assert!(matches!(
+                display.fill_solid_with_scratch(&area, Rgb565::RED, &mut [0; 1]),
+                Err(Error::BufferSize)
+            ));
+        }
+        let mut gamma: GammaTable = core::array::from_fn(|i| i as u8);
+        gamma[10] = 0;
+        assert!(matches!(display.set_gamma(&gamma), Err(Error::InvalidData)));
+        assert!(matches!(
+            display.set_precharge_voltage_level(0x20),
+            Err(Error::OutOfBounds)
+        ));
+        assert_eq!(mock.take(), []);
+
+        mock.set_fail(true);
+        assert!(matches!(
+            display.set_master_contrast(0x08),
+            Err(Error::Interface(DisplayError::BusWriteError))
+        ));
+        mock.set_fail(false);
+
+        // Both panel sizes have every optional feature, so no call returns
+        // `Error::Unsupported` with the displays supported today
+        for size in [DisplaySize::Display128x128, DisplaySize::Display128x96] {
+            let (mut display, _) = mock::graphics(size, DisplayRotation::Rotate0);
+            display
+                .set_gpio(GpioMode::OutputHigh, GpioMode::InputDisabled)
+                .unwrap();
+            display.set_color_mode(ColorMode::Rgb666).unwrap();
+        }
+    }
+}
@@ -0,0 +1,195 @@
+//! Framebuffer storage and pixel format
+//!
+//! By default the framebuffer stores every pixel as two big endian RGB565 bytes, exactly as it is
+//! sent to the display, or little endian after
+//! [`set_byte_order`](crate::mode::GraphicsMode::set_byte_order). With the `palette8` feature each
+//! pixel is instead stored as a single RGB332 byte (3 bits red, 3 bits green, 2 bits blue),
+//! halving the memory needed at the cost of colour fidelity, and is expanded back to RGB565 while
+//! flushing.
+
+use crate::properties::{ByteOrder, DisplaySize};
+
+/// Number of framebuffer bytes used per pixel
+#[cfg(not(feature = "palette8"))]
+pub const BYTES_PER_PIXEL: usize = 2;
+
+/// Number of framebuffer bytes used per pixel
+#[cfg(feature = "palette8")]
+pub const BYTES_PER_PIXEL: usize = 1;
+
+/// Convert an RGB565 colour into its framebuffer representation
+#[cfg(not(feature = "palette8"))]
+pub fn encode_pixel(color: u16) -> [u8; BYTES_PER_PIXEL] {
+    color.to_be_bytes()
+}
+
+/// Convert an RGB565 colour into its framebuffer representation
+#[cfg(feature = "palette8")]
+pub fn encode_pixel(color: u16) -> [u8; BYTES_PER_PIXEL] {
+    [rgb565_to_rgb332(color)]
+}
+
+/// Convert a pixel in framebuffer representation back to an RGB565 colour
+#[cfg(not(feature = "palette8"))]
+pub fn decode_pixel(pixel: [u8; BYTES_PER_PIXEL]) -> u16 {
+    u16::from_be_bytes(pixel)
+}
+
+/// Convert a pixel in framebuffer representation back to an RGB565 colour
+#[cfg(feature = "palette8")]
+pub fn decode_pixel(pixel: [u8; BYTES_PER_PIXEL]) -> u16 {
+    rgb332_to_rgb565(pixel[0])
+}
+
+/// Convert an RGB565 colour into its framebuffer representation with the bytes in `order`, see
+/// [`GraphicsMode::set_byte_order`](crate::mode::GraphicsMode::set_byte_order)
+#[cfg(not(feature = "palette8"))]
+pub fn encode_pixel_with(color: u16, order: ByteOrder) -> [u8; BYTES_PER_PIXEL] {
+    order.write(color)
+}
+
+/// Convert an RGB565 colour into its framebuffer representation, RGB332 pixels are a single byte
+/// so `order` does not apply
+#[cfg(feature = "palette8")]
+pub fn encode_pixel_with(color: u16, _order: ByteOrder) -> [u8; BYTES_PER_PIXEL] {
+    encode_pixel(color)
+}
+
+/// Convert a pixel with the bytes in `order` back to an RGB565 colour
+#[cfg(not(feature = "palette8"))]
+pub fn decode_pixel_with(pixel: [u8; BYTES_PER_PIXEL], order: ByteOrder) -> u16 {
+    order.read(pixel)
+}
+
+/// Convert a pixel back to an RGB565 colour, RGB332 pixels are a single byte so `order` does not
+/// apply
+#[cfg(feature = "palette8")]
+pub fn decode_pixel_with(pixel: [u8; BYTES_PER_PIXEL], _order: ByteOrder) -> u16 {
+    decode_pixel(pixel)
+}
+
+/// Scale the channels of an RGB565 colour by `r`, `g` and `b` out of 255
+pub(crate) fn tint_pixel(color: u16, (r, g, b): (u8, u8, u8)) -> u16 {
+    let scale = |channel: u16, factor: u8| (channel as u32 * factor as u32 / 255) as u16;
+    let red = scale(color >> 11, r);
+    let green = scale(color >> 5 & 0x3F, g);
+    let blue = scale(color & 0x1F, b);
+    red << 11 | green << 5 | blue
+}
+
+/// Turn an RGB565 colour into the gray of the same luma, weighting the channels 77:150:29. Red
+/// and blue are widened to 6 bits first so that white stays white.
+pub(crate) fn gray_pixel(color: u16) -> u16 {
+    let widen = |channel: u16| (channel << 1 | channel >> 4) as u32;
+    let red = widen(color >> 11);
+    let green = (color >> 5 & 0x3F) as u32;
+    let blue = widen(color & 0x1F);
+    let luma = ((red * 77 + green * 150 + blue * 29) >> 8) as u16;
+    (luma >> 1) << 11 | luma << 5 | luma >> 1
+}
+
+/// Reduce an RGB565 colour to RGB332 by dropping the lower bits of each channel
+#[cfg(feature = "palette8")]
+pub fn rgb565_to_rgb332(color: u16) -> u8 {
+    let r = (color >> 13) as u8 & 0x07;
+    let g = (color >> 8) as u8 & 0x07;
+    let b = (color >> 3) as u8 & 0x03;
+    r << 5 | g << 2 | b
+}
+
+/// Expand an RGB332 colour to RGB565, repeating the high bits of each channel in the low bits so
+/// that full intensity stays full intensity
+#[cfg(feature = "palette8")]
+pub fn rgb332_to_rgb565(color: u8) -> u16 {
+    let r = (color >> 5) as u16 & 0x07;
+    let g = (color >> 2) as u16 & 0x07;
+    let b = color as u16 & 0x03;
+    let r = r << 2 | r >> 1;
+    let g = g << 3 | g;
+    let b = b << 3 | b << 1 | b >> 1;
+    r << 11 | g << 5 | b
+}
+
+/// Framebuffer for a `W` x `H` pixel display, holding every pixel in the format described in the
+/// [module documentation](self).
+///
+/// As the size is part of the type, a framebuffer can be declared with exactly the size of the
+/// display it is used with, e.g. `FrameBuffer<128, 96>` for a 128x96 display. Sizes no display
+/// has fail to compile when passed to
+/// [`new_with_framebuffer`](crate::mode::GraphicsMode::new_with_framebuffer), and the borrow can
+/// be of any lifetime, so a framebuffer on the stack works as well as a `static` one.
+pub struct FrameBuffer<const W: usize, const H: usize> {
+    data: [[[u8; BYTES_PER_PIXEL]; W]; H],
+}
+
+impl<const W: usize, const H: usize> FrameBuffer<W, H> {
+    /// The display size this framebuffer is for, evaluating it for a size no display has is a
+    /// compile error
+    pub const DISPLAY_SIZE: DisplaySize = match (W, H) {
+        (128, 128) => DisplaySize::Display128x128,
+        (128, 96) => DisplaySize::Display128x96,
+        _ => panic!("FrameBuffer size does not match any display size"),
+    };
+
+    /// Create a new, all black framebuffer
+    pub const fn new() -> Self {
+        Self {
+            data: [[[0; BYTES_PER_PIXEL]; W]; H],
+        }
+    }
+
+    /// Access the raw bytes of the framebuffer
+    pub fn as_slice(&self) -> &[u8] {
+        self.data.as_flattened().as_flattened()
+    }
+
+    /// Mutably access the raw bytes of the framebuffer
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        self.data.as_flattened_mut().as_flattened_mut()
+    }
+}
+
+impl<const W: usize, const H: usize> Default for FrameBuffer<W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gray_pixel_keeps_the_luma_of_known_colors() {
+        assert_eq!(gray_pixel(0x0000), 0x0000);
+        assert_eq!(gray_pixel(0xFFFF), 0xFFFF);
+        // Grays stay as they are
+        assert_eq!(gray_pixel(0x8410), 0x8410);
+        assert_eq!(gray_pixel(0xF800), 0x4A49);
+        assert_eq!(gray_pixel(0x07E0), 0x9492);
+        assert_eq!(gray_pixel(0x001F), 0x18E3);
+    }
+
+    #[cfg(feature = "palette8")]
+    #[test]
+    fn rgb332_survives_a_round_trip_through_rgb565() {
+        for color in 0..=u8::MAX {
+            assert_eq!(rgb565_to_rgb332(rgb332_to_rgb565(color)), color);
+        }
+    }
+
+    #[cfg(feature = "palette8")]
+    #[test]
+    fn rgb565_quantises_at_the_channel_extremes() {
+        assert_eq!(rgb565_to_rgb332(0x0000), 0x00);
+        assert_eq!(rgb565_to_rgb332(0xFFFF), 0xFF);
+        assert_eq!(rgb565_to_rgb332(0xF800), 0xE0);
+        assert_eq!(rgb565_to_rgb332(0x07E0), 0x1C);
+        assert_eq!(rgb565_to_rgb332(0x001F), 0x03);
+        // Just below the lowest step of every channel
+        assert_eq!(rgb565_to_rgb332(0x1800 | 0x00E0 | 0x0007), 0x00);
+        assert_eq!(rgb332_to_rgb565(0xE0), 0xF800);
+        assert_eq!(rgb332_to_rgb565(0x1C), 0x07E0);
+        assert_eq!(rgb332_to_rgb565(0x03), 0x001F);
+    }
+}
@@ -0,0 +1,24 @@
+//! Building the interface for the common SPI wiring
+//!
+//! Most SSD1351 modules are wired for 4-wire SPI: clock and MOSI (often labelled `CLK` and `DIN`)
+//! from the SPI bus, a chip select line and a D/C pin telling commands (low) from data (high). The
+//! reset line is separate, see [`GraphicsMode::reset`](crate::mode::GraphicsMode::reset). The
+//! panel samples on the rising clock edge, use
+//! [`SSD1351_SPI_MODE`](crate::prelude::SSD1351_SPI_MODE) at up to 20 MHz.
+
+use display_interface_spi::SPIInterface;
+use hal::digital::OutputPin;
+use hal::spi::SpiDevice;
+
+/// Build the interface for a panel on an SPI device and a D/C pin, ready to connect through
+/// [`Builder::connect_interface`](crate::builder::Builder::connect_interface). Chip select is
+/// taken care of by the `SpiDevice`, e.g. an `ExclusiveDevice` from `embedded-hal-bus` wrapping
+/// the bus and the CS pin, so that each transfer asserts it. For any other wiring build a
+/// `display_interface` implementation directly.
+pub fn spi<SPI, DC>(spi: SPI, dc: DC) -> SPIInterface<SPI, DC>
+where
+    SPI: SpiDevice,
+    DC: OutputPin,
+{
+    SPIInterface::new(spi, dc)
+}
@@ -1,11 +1,28 @@
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 #![allow(clippy::result_unit_err)]
 
 extern crate embedded_hal as hal;
 
 pub mod builder;
+#[cfg(feature = "graphics")]
+pub mod color;
 pub mod command;
 pub mod display;
+pub mod error;
+mod font;
+#[cfg(feature = "buffered")]
+pub mod framebuffer;
+#[cfg(feature = "spi")]
+pub mod interface;
+#[cfg(test)]
+mod mock;
 pub mod mode;
+pub mod multi;
+pub mod pacer;
+#[cfg(feature = "buffered")]
+pub mod paged;
 pub mod prelude;
 pub mod properties;
+#[cfg(feature = "shared")]
+pub mod shared;
+pub mod video;
@@ -0,0 +1,257 @@
+//! Recording interface for the unit tests
+//!
+//! [`MockInterface`] keeps every transfer the driver makes, with the DC level it was sent at, so
+//! that tests can check both the bytes on the bus and how many transfers they took.
+
+// Not every feature set has tests using every helper
+#![allow(dead_code)]
+
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::vec::Vec;
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use hal::delay::DelayNs;
+use hal::digital::{ErrorType, OutputPin, PinState};
+
+use crate::display::Display;
+#[cfg(feature = "buffered")]
+use crate::framebuffer::{decode_pixel_with, BYTES_PER_PIXEL};
+use crate::mode::displaymode::DisplayModeTrait;
+use crate::mode::GraphicsMode;
+use crate::properties::{DisplayRotation, DisplaySize};
+
+/// Command byte of `WriteRam`, the data after it goes into display RAM
+const WRITE_RAM: u8 = 0x5C;
+
+/// One transfer seen by a [`MockInterface`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Transfer {
+    /// Bytes sent through `send_commands`, with DC low
+    Command(Vec<u8>),
+    /// Bytes sent through `send_data`, with DC high
+    Data(Vec<u8>),
+}
+
+/// Interface recording every transfer. Clones share the log, so one clone can be kept to read
+/// it after the other has been moved into the display.
+#[derive(Clone, Default)]
+pub struct MockInterface {
+    log: Rc<RefCell<Vec<Transfer>>>,
+    fail: Rc<Cell<bool>>,
+}
+
+impl MockInterface {
+    /// Return the transfers recorded so far and forget them
+    pub fn take(&self) -> Vec<Transfer> {
+        self.log.take()
+    }
+
+    /// Make every following transfer fail with `DisplayError::BusWriteError` without recording
+    /// it, until called again with `false`
+    pub fn set_fail(&self, fail: bool) {
+        self.fail.set(fail);
+    }
+
+    fn record(&self, transfer: Transfer) -> Result<(), DisplayError> {
+        if self.fail.get() {
+            return Err(DisplayError::BusWriteError);
+        }
+        self.log.borrow_mut().push(transfer);
+        Ok(())
+    }
+}
+
+impl WriteOnlyDataCommand for MockInterface {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.record(Transfer::Command(bytes(cmd)?))
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.record(Transfer::Data(bytes(buf)?))
+    }
+}
+
+fn bytes(format: DataFormat<'_>) -> Result<Vec<u8>, DisplayError> {
+    match format {
+        DataFormat::U8(data) => Ok(data.to_vec()),
+        DataFormat::U8Iter(iter) => Ok(iter.collect()),
+        _ => Err(DisplayError::DataFormatNotImplemented),
+    }
+}
+
+/// 8 bit 8080 style parallel bus clocking one byte per write strobe, the way
+/// `display-interface-parallel-gpio` drives it. Records the DC level and the byte of every cycle.
+#[derive(Clone, Default)]
+pub struct MockParallelBus {
+    cycles: Rc<RefCell<Vec<(bool, u8)>>>,
+}
+
+impl MockParallelBus {
+    /// Return the bus cycles recorded so far and forget them
+    pub fn take(&self) -> Vec<(bool, u8)> {
+        self.cycles.take()
+    }
+
+    fn clock(&self, dc: bool, format: DataFormat<'_>) -> Result<(), DisplayError> {
+        let mut cycles = self.cycles.borrow_mut();
+        match format {
+            DataFormat::U8(data) => cycles.extend(data.iter().map(|&byte| (dc, byte))),
+            DataFormat::U8Iter(iter) => cycles.extend(iter.map(|byte| (dc, byte))),
+            DataFormat::U16BEIter(iter) => cycles.extend(
+                iter.flat_map(|word| word.to_be_bytes())
+                    .map(|byte| (dc, byte)),
+            ),
+            _ => return Err(DisplayError::DataFormatNotImplemented),
+        }
+        Ok(())
+    }
+}
+
+impl WriteOnlyDataCommand for MockParallelBus {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.clock(false, cmd)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.clock(true, buf)
+    }
+}
+
+/// Output pin recording every level it is set to
+#[derive(Default)]
+pub struct MockPin {
+    pub levels: Vec<PinState>,
+}
+
+impl ErrorType for MockPin {
+    type Error = core::convert::Infallible;
+}
+
+impl OutputPin for MockPin {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.levels.push(PinState::Low);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.levels.push(PinState::High);
+        Ok(())
+    }
+}
+
+/// Delay returning at once, adding up the time it was asked to wait
+#[derive(Default)]
+pub struct MockDelay {
+    pub total_ns: u64,
+}
+
+impl DelayNs for MockDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.total_ns += ns as u64;
+    }
+}
+
+/// Waker doing nothing, the futures under test are polled in a loop
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
+
+/// Poll `future` once
+pub fn poll_once<F: Future>(future: Pin<&mut F>) -> Poll<F::Output> {
+    let waker = Waker::from(Arc::new(NoopWaker));
+    future.poll(&mut Context::from_waker(&waker))
+}
+
+/// Poll `future` until it is ready
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(output) = poll_once(future.as_mut()) {
+            return output;
+        }
+    }
+}
+
+/// A display talking to a fresh mock, and a handle on the mock
+pub fn display(
+    size: DisplaySize,
+    rotation: DisplayRotation,
+) -> (Display<MockInterface>, MockInterface) {
+    let mock = MockInterface::default();
+    (Display::new(mock.clone(), size, rotation), mock)
+}
+
+#[cfg(not(feature = "buffered"))]
+/// A graphics mode talking to a fresh mock, and a handle on the mock
+pub fn graphics(
+    size: DisplaySize,
+    rotation: DisplayRotation,
+) -> (GraphicsMode<'static, MockInterface>, MockInterface) {
+    let (display, mock) = display(size, rotation);
+    (GraphicsMode::new(display), mock)
+}
+
+#[cfg(feature = "buffered")]
+/// A graphics mode with a fresh, all black framebuffer talking to a fresh mock, and a handle on
+/// the mock
+pub fn graphics(
+    size: DisplaySize,
+    rotation: DisplayRotation,
+) -> (GraphicsMode<'static, MockInterface>, MockInterface) {
+    let (display, mock) = display(size, rotation);
+    let buffer = vec![0; size.num_pixels() * BYTES_PER_PIXEL].leak();
+    (GraphicsMode::new(display, buffer), mock)
+}
+
+/// The command bytes of `transfers` in the order they were sent
+pub fn commands(transfers: &[Transfer]) -> Vec<u8> {
+    transfers
+        .iter()
+        .filter_map(|transfer| match transfer {
+            Transfer::Command(bytes) => Some(bytes.as_slice()),
+            Transfer::Data(_) => None,
+        })
+        .flatten()
+        .copied()
+        .collect()
+}
+
+/// The arguments of every `command` in `transfers`, i.e. the data following it
+pub fn args(transfers: &[Transfer], command: u8) -> Vec<Vec<u8>> {
+    let mut args = Vec::new();
+    let mut last = None;
+    for transfer in transfers {
+        match transfer {
+            Transfer::Command(bytes) => last = bytes.last().copied(),
+            Transfer::Data(bytes) if last == Some(command) => args.push(bytes.clone()),
+            Transfer::Data(_) => {}
+        }
+    }
+    args
+}
+
+/// The data transfers following `WriteRam` in `transfers`, i.e. what lands in display RAM
+pub fn ram_writes(transfers: &[Transfer]) -> Vec<Vec<u8>> {
+    args(transfers, WRITE_RAM)
+}
+
+/// All bytes written to display RAM in `transfers`, back to back
+pub fn ram_data(transfers: &[Transfer]) -> Vec<u8> {
+    ram_writes(transfers).concat()
+}
+
+#[cfg(feature = "buffered")]
+/// The RGB565 colour of the framebuffer pixel at `x`, `y` in the current orientation
+pub fn pixel(display: &GraphicsMode<'_, MockInterface>, x: usize, y: usize) -> u16 {
+    let width = display.get_dimensions().0 as usize;
+    let i = (y * width + x) * BYTES_PER_PIXEL;
+    let pixel = display.fb()[i..i + BYTES_PER_PIXEL].try_into().unwrap();
+    decode_pixel_with(pixel, display.byte_order())
+}
@@ -9,29 +9,29 @@ pub struct DisplayMode<MODE> {
 }
 
 /// Trait with core functionality for display switching
-pub trait DisplayModeTrait<DI> {
+pub trait DisplayModeTrait<'a, DI> {
     /// Allocate all required data and initialise display for display
     #[cfg(not(feature = "buffered"))]
     fn new(display: Display<DI>) -> Self;
 
     #[cfg(feature = "buffered")]
-    fn new(display: Display<DI>, buffer: &'static mut [u8]) -> Self;
+    fn new(display: Display<DI>, buffer: &'a mut [u8]) -> Self;
 
     /// Release resources for reuse with different display
     #[cfg(not(feature = "buffered"))]
     fn release(self) -> Display<DI>;
 
     #[cfg(feature = "buffered")]
-    fn release(self) -> (Display<DI>, &'static mut [u8]);
+    fn release(self) -> (Display<DI>, &'a mut [u8]);
 }
 
 impl<MODE> DisplayMode<MODE> {
     /// Setup display to run in requested display
     #[cfg(not(feature = "buffered"))]
-    pub fn new<DI>(display: Display<DI>) -> Self
+    pub fn new<'a, DI>(display: Display<DI>) -> Self
     where
         DI: WriteOnlyDataCommand,
-        MODE: DisplayModeTrait<DI>,
+        MODE: DisplayModeTrait<'a, DI>,
     {
         DisplayMode {
             display: MODE::new(display),
@@ -39,10 +39,10 @@ impl<MODE> DisplayMode<MODE> {
     }
 
     #[cfg(feature = "buffered")]
-    pub fn new<DI>(display: Display<DI>, buffer: &'static mut [u8]) -> Self
+    pub fn new<'a, DI>(display: Display<DI>, buffer: &'a mut [u8]) -> Self
     where
         DI: WriteOnlyDataCommand,
-        MODE: DisplayModeTrait<DI>,
+        MODE: DisplayModeTrait<'a, DI>,
     {
         DisplayMode {
             display: MODE::new(display, buffer),
@@ -52,20 +52,20 @@ impl<MODE> DisplayMode<MODE> {
     /// Change into any display implementing DisplayModeTrait
     // TODO: Figure out how to stay as generic DisplayMode but act as particular display
     #[cfg(not(feature = "buffered"))]
-    pub fn into<DI, NMODE: DisplayModeTrait<DI>>(self) -> NMODE
+    pub fn into<'a, DI, NMODE: DisplayModeTrait<'a, DI>>(self) -> NMODE
     where
         DI: WriteOnlyDataCommand,
-        MODE: DisplayModeTrait<DI>,
+        MODE: DisplayModeTrait<'a, DI>,
     {
         let display = self.display.release();
         NMODE::new(display)
     }
 
     #[cfg(feature = "buffered")]
-    pub fn into<DI, NMODE: DisplayModeTrait<DI>>(self) -> NMODE
+    pub fn into<'a, DI, NMODE: DisplayModeTrait<'a, DI>>(self) -> NMODE
     where
         DI: WriteOnlyDataCommand,
-        MODE: DisplayModeTrait<DI>,
+        MODE: DisplayModeTrait<'a, DI>,
     {
         let (display, buffer) = self.display.release();
         NMODE::new(display, buffer)
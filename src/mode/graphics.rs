@@ -1,34 +1,135 @@
-use crate::display::Display;
+use crate::display::{Display, DisplayConfig, GammaTable, InitStats, StreamGuard};
+use crate::error::Error;
+use crate::font::{self, CHAR_HEIGHT, CHAR_WIDTH};
+#[cfg(feature = "buffered")]
+use crate::framebuffer::{
+    decode_pixel_with, encode_pixel_with, gray_pixel, tint_pixel, FrameBuffer, BYTES_PER_PIXEL,
+};
 use display_interface::{DisplayError, WriteOnlyDataCommand};
 use hal::delay::DelayNs;
-use hal::digital::OutputPin;
+use hal::digital::{OutputPin, PinState};
 
 use crate::mode::displaymode::DisplayModeTrait;
-use crate::properties::DisplayRotation;
+use crate::properties::{
+    ByteOrder, Capabilities, ColorMode, CommandLockMode, DisplayProfile, DisplayRotation, GpioMode,
+    Region, TestPattern,
+};
+#[cfg(not(feature = "buffered"))]
+use core::marker::PhantomData;
+#[cfg(feature = "stream")]
+use core::{future::poll_fn, pin::Pin};
+#[cfg(feature = "stream")]
+use futures_core::Stream;
+
+/// Master contrast set by `mono_mode`
+#[cfg(feature = "buffered")]
+const MONO_CONTRAST: u8 = 4;
+
+/// Scale out of 255 that `blank` applies to every channel
+#[cfg(feature = "buffered")]
+const BLANK_SCALE: u8 = 64;
+
+/// Number of rows that `flush_yielding` sends between two yield points
+#[cfg(feature = "buffered")]
+const FLUSH_YIELD_ROWS: u8 = 16;
+
+/// Height of the bands suggested by `preferred_chunk_size`
+#[cfg(feature = "graphics")]
+const PREFERRED_CHUNK_ROWS: u32 = 8;
+
+/// Number of pixels that `draw_iter` sorts into runs at a time without a framebuffer
+#[cfg(all(feature = "graphics", not(feature = "buffered")))]
+const DRAW_ITER_BATCH: usize = 64;
 
 /// Graphics Mode for the display
-pub struct GraphicsMode<DI>
+pub struct GraphicsMode<'a, DI>
 where
     DI: WriteOnlyDataCommand,
 {
     display: Display<DI>,
     #[cfg(feature = "buffered")]
-    pub buffer: &'static mut [u8],
+    pub buffer: &'a mut [u8],
+    /// No framebuffer without the `buffered` feature, the lifetime keeps the type the same
+    #[cfg(not(feature = "buffered"))]
+    buffer: PhantomData<&'a mut [u8]>,
+    /// Whether the full screen window is programmed and the RAM position is back at its start
+    #[cfg(feature = "buffered")]
+    continuous_window: bool,
+    /// Whether rows are mirrored while flushing
+    #[cfg(feature = "buffered")]
+    flip_horizontal: bool,
+    /// Red, green and blue scale applied while flushing
+    #[cfg(feature = "buffered")]
+    tint: Option<(u8, u8, u8)>,
+    /// Master contrast to restore once mono mode is turned off, `None` while it is off
+    #[cfg(feature = "buffered")]
+    mono_contrast: Option<u8>,
+    /// One bit per framebuffer row changed since it was last flushed
+    #[cfg(feature = "buffered")]
+    dirty_rows: u128,
+    /// Whether every embedded-graphics draw is flushed right away
+    #[cfg(feature = "buffered")]
+    auto_flush: bool,
+    /// Whether `set_pixel` may continue at the RAM position left by the previous pixel
+    #[cfg(not(feature = "buffered"))]
+    pixel_run: bool,
+    /// Pixels outside of this area are dropped while drawing
+    #[cfg(feature = "graphics")]
+    clip: Option<Rectangle>,
+    /// Whether colours of more than 16 bits are dithered down to RGB565
+    #[cfg(feature = "graphics")]
+    dither: bool,
+    /// Byte order of the framebuffer and of `draw_raw` data
+    byte_order: ByteOrder,
 }
 
-impl<DI> DisplayModeTrait<DI> for GraphicsMode<DI>
+impl<'a, DI> DisplayModeTrait<'a, DI> for GraphicsMode<'a, DI>
 where
     DI: WriteOnlyDataCommand,
 {
     #[cfg(not(feature = "buffered"))]
     /// Create new GraphicsMode instance
     fn new(display: Display<DI>) -> Self {
-        GraphicsMode { display }
+        GraphicsMode {
+            display,
+            buffer: PhantomData,
+            pixel_run: false,
+            #[cfg(feature = "graphics")]
+            clip: None,
+            #[cfg(feature = "graphics")]
+            dither: false,
+            byte_order: ByteOrder::BigEndian,
+        }
     }
 
     #[cfg(feature = "buffered")]
-    fn new(display: Display<DI>, buffer: &'static mut [u8]) -> Self {
-        GraphicsMode { display, buffer }
+    /// Create new GraphicsMode instance
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer does not hold `BYTES_PER_PIXEL` bytes for every pixel of the display,
+    /// as all pixel indexing is derived from the display dimensions.
+    fn new(display: Display<DI>, buffer: &'a mut [u8]) -> Self {
+        assert_eq!(
+            buffer.len(),
+            display.get_size().num_pixels() * BYTES_PER_PIXEL,
+            "framebuffer does not match the display size"
+        );
+        GraphicsMode {
+            display,
+            buffer,
+            continuous_window: false,
+            flip_horizontal: false,
+            tint: None,
+            mono_contrast: None,
+            dirty_rows: 0,
+            auto_flush: false,
+            #[cfg(feature = "graphics")]
+            clip: None,
+            #[cfg(feature = "graphics")]
+            dither: false,
+            byte_order: ByteOrder::BigEndian,
+        }
     }
 
     #[cfg(not(feature = "buffered"))]
@@ -38,8 +139,9 @@ where
     }
 
     #[cfg(feature = "buffered")]
-    /// Release all resources used by GraphicsMode
-    fn release(self) -> (Display<DI>, &'static mut [u8]) {
+    /// Release all resources used by GraphicsMode. Unflushed changes stay in the buffer and never
+    /// reach the display, see `flush_and_release` to send them first.
+    fn release(self) -> (Display<DI>, &'a mut [u8]) {
         (self.display, self.buffer)
     }
 }
@@ -51,43 +153,163 @@ where
 //     }
 // }
 
-impl<DI> GraphicsMode<DI>
+impl<'a, DI> GraphicsMode<'a, DI>
 where
     DI: WriteOnlyDataCommand,
 {
+    #[cfg(feature = "buffered")]
+    /// Create a new GraphicsMode instance drawing into the given framebuffer. The framebuffer size
+    /// is checked against the display sizes at compile time, a framebuffer for the other display
+    /// size is handed back together with the display as the error.
+    #[allow(clippy::result_large_err)]
+    pub fn new_with_framebuffer<const W: usize, const H: usize>(
+        display: Display<DI>,
+        framebuffer: &'a mut FrameBuffer<W, H>,
+    ) -> Result<Self, (Display<DI>, &'a mut FrameBuffer<W, H>)> {
+        let size = const { FrameBuffer::<W, H>::DISPLAY_SIZE };
+        if size.dimensions() != display.get_size().dimensions() {
+            return Err((display, framebuffer));
+        }
+        Ok(DisplayModeTrait::new(display, framebuffer.as_mut_slice()))
+    }
+
     #[cfg(not(feature = "buffered"))]
     /// Clear the display
-    pub fn clear(&mut self) {
-        self.display.clear().unwrap();
+    pub fn clear(&mut self) -> Result<(), Error> {
+        self.display.clear()
     }
 
     #[cfg(feature = "buffered")]
     /// Clear the display
-    pub fn clear(&mut self, flush: bool) {
+    pub fn clear(&mut self, flush: bool) -> Result<(), Error> {
         for i in 0..self.buffer.len() {
             self.buffer[i] = 0u8;
         }
+        self.dirty_rows = u128::MAX;
         if flush {
-            self.flush();
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "buffered"))]
+    /// Set all pixels of the display to `color`, see [`Display::clear_to`]
+    pub fn clear_to(&mut self, color: u16) -> Result<(), Error> {
+        self.display.clear_to(color)
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Set all pixels of the framebuffer to `color`, call `flush` to show it
+    pub fn clear_to(&mut self, color: u16) -> Result<(), Error> {
+        let pixel = encode_pixel_with(color, self.byte_order);
+        for chunk in self.buffer.chunks_exact_mut(BYTES_PER_PIXEL) {
+            chunk.copy_from_slice(&pixel);
         }
+        self.dirty_rows = u128::MAX;
+        Ok(())
     }
 
-    /// Reset display
+    /// Reset display. The pulse polarity follows the reset level configured through
+    /// [`Builder::with_reset_active_low`](crate::builder::Builder::with_reset_active_low), by
+    /// default the line is pulled low to reset.
     pub fn reset<RST, DELAY>(&mut self, rst: &mut RST, delay: &mut DELAY) -> Result<(), RST::Error>
     where
         RST: OutputPin,
         DELAY: DelayNs,
     {
-        rst.set_high()?;
+        let (inactive, active) = if self.display.reset_active_low() {
+            (PinState::High, PinState::Low)
+        } else {
+            (PinState::Low, PinState::High)
+        };
+        rst.set_state(inactive)?;
         delay.delay_ms(1);
-        rst.set_low()?;
+        rst.set_state(active)?;
         delay.delay_ms(10);
-        rst.set_high()?;
+        rst.set_state(inactive)?;
+        self.display.invalidate_draw_area();
         Ok(())
     }
 
     #[cfg(feature = "buffered")]
-    /// Access the framebuffer
+    /// Whether a framebuffer of the right size is attached. The buffer can be swapped out through
+    /// the public `buffer` field, e.g. by code that takes it away to compress it. Without it,
+    /// drawing is a no-op and `flush`, `flush_and_release`, `flush_continuous` and `flush_area`
+    /// return `Error::BufferSize` instead of indexing into the missing buffer.
+    pub fn has_buffer(&self) -> bool {
+        self.buffer.len() == self.display.get_size().num_pixels() * BYTES_PER_PIXEL
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Take the framebuffer away, e.g. to reuse its RAM while the display is idle or to hold a
+    /// compressed copy of the frame, leaving the display without one (see
+    /// [`has_buffer`](Self::has_buffer)). Returns `None` if there is no framebuffer to take. Hand
+    /// it back through [`restore_buffer`](Self::restore_buffer), unlike dropping the reference
+    /// this never loses the memory.
+    pub fn take_buffer(&mut self) -> Option<&'a mut [u8]> {
+        if !self.has_buffer() {
+            return None;
+        }
+        self.continuous_window = false;
+        Some(core::mem::take(&mut self.buffer))
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Attach a framebuffer again after [`take_buffer`](Self::take_buffer). Its contents are
+    /// taken as they are and sent in full on the next `flush_dirty_rows`. Hands `buffer` back as
+    /// the error if it does not match the size of the display.
+    pub fn restore_buffer(&mut self, buffer: &'a mut [u8]) -> Result<(), &'a mut [u8]> {
+        if buffer.len() != self.display.get_size().num_pixels() * BYTES_PER_PIXEL {
+            return Err(buffer);
+        }
+        self.buffer = buffer;
+        self.dirty_rows = u128::MAX;
+        Ok(())
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Copy the framebuffer into `into`, e.g. to restore the screen after an overlay or to undo
+    /// a change. Returns `Error::BufferSize` if there is no framebuffer or `into` is not exactly
+    /// as long as it.
+    pub fn snapshot(&self, into: &mut [u8]) -> Result<(), Error> {
+        if !self.has_buffer() || into.len() != self.buffer.len() {
+            return Err(Error::BufferSize);
+        }
+        into.copy_from_slice(self.buffer);
+        Ok(())
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Copy a [`snapshot`](Self::snapshot) back into the framebuffer and mark every row for the
+    /// next flush. Returns `Error::BufferSize` if there is no framebuffer or `from` is not
+    /// exactly as long as it.
+    pub fn restore(&mut self, from: &[u8]) -> Result<(), Error> {
+        if !self.has_buffer() || from.len() != self.buffer.len() {
+            return Err(Error::BufferSize);
+        }
+        self.buffer.copy_from_slice(from);
+        self.dirty_rows = u128::MAX;
+        Ok(())
+    }
+
+    /// Set the byte order of the pixels in the framebuffer (with the `buffered` feature) and of the
+    /// data handed to `draw_raw`, e.g. to reuse a little endian `u16` framebuffer ported from
+    /// another driver. The display itself always takes big endian data, other orders are swapped
+    /// while flushing or drawing at the cost of a copy. Defaults to `ByteOrder::BigEndian`.
+    ///
+    /// Pixels already in the framebuffer are not converted, set the order before drawing. It has
+    /// no effect on `palette8` framebuffers, whose pixels are a single byte.
+    pub fn set_byte_order(&mut self, order: ByteOrder) {
+        self.byte_order = order;
+    }
+
+    /// Get the byte order set through [`set_byte_order`](Self::set_byte_order)
+    pub fn byte_order(&self) -> ByteOrder {
+        self.byte_order
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Access the framebuffer, see [`framebuffer`](crate::framebuffer) for its format
     pub fn fb(&self) -> &[u8] {
         self.buffer
     }
@@ -98,133 +320,3705 @@ where
         self.buffer
     }
 
+    #[cfg(feature = "buffered")]
+    /// Compute a 32 bit FNV-1a hash of the framebuffer, e.g. to skip sending an unchanged frame to
+    /// a remote renderer. Any change to the framebuffer changes the hash with high probability, it
+    /// is not meant to guard against deliberate collisions. The hash covers the raw bytes, so a
+    /// different byte order or `palette8` gives different hashes for the same image.
+    pub fn frame_hash(&self) -> u32 {
+        const FNV_OFFSET_BASIS: u32 = 0x811C_9DC5;
+        const FNV_PRIME: u32 = 0x0100_0193;
+
+        self.buffer.iter().fold(FNV_OFFSET_BASIS, |hash, &byte| {
+            (hash ^ byte as u32).wrapping_mul(FNV_PRIME)
+        })
+    }
+
     #[cfg(not(feature = "buffered"))]
-    /// Turn a pixel on or off. A non-zero `value` is treated as on, `0` as off. If the X and Y
-    /// coordinates are out of the bounds of the display, this method call is a noop.
-    pub fn set_pixel(&mut self, x: u32, y: u32, color: u16) {
-        let (display_width, display_height) = self.display.get_size().dimensions();
-        let rot = self.display.get_rotation();
-        let (nx, ny) = match rot {
-            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (x, y),
-            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => (y, x),
-        };
-        self.display
-            .set_draw_area((nx as u8, ny as u8), (display_width, display_height))
-            .unwrap();
-        self.display
-            .draw(&[(color >> 8) as u8, color as u8])
-            .unwrap();
+    /// Set the pixel at `x`, `y` to the RGB565 `color`. If the X and Y coordinates are out of the
+    /// bounds of the display, this method call is a noop.
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: u16) -> Result<(), Error> {
+        let (display_width, display_height) = self.display.get_dimensions();
+        if x >= display_width as u32 || y >= display_height as u32 || self.is_clipped(x, y) {
+            return Ok(());
+        }
+        let (ram_position, _) = self
+            .display
+            .rotate_area((x as u8, y as u8), (x as u8, y as u8));
+        if !self.pixel_run || self.display.current_position() != Some(ram_position) {
+            self.display
+                .set_draw_area_logical((x as u8, y as u8), (display_width, display_height))?;
+        }
+        self.display.draw(&[(color >> 8) as u8, color as u8])
+    }
+
+    #[cfg(not(feature = "buffered"))]
+    /// Start a run of `set_pixel` calls that leaves the draw window open between pixels. A pixel
+    /// landing where the display is going to write next anyway, e.g. the next one to the right,
+    /// is sent without the column, row and `WriteRam` commands, one transfer instead of six. Any
+    /// other command sent before [`end_pixels`](Self::end_pixels), e.g. by `set_master_contrast`
+    /// or through `interface_mut`, ends the RAM write on the panel. Drawing through the driver is
+    /// fine as it programs its own window.
+    pub fn begin_pixels(&mut self) {
+        self.pixel_run = true;
+    }
+
+    #[cfg(not(feature = "buffered"))]
+    /// End a run started with [`begin_pixels`](Self::begin_pixels), every `set_pixel` programs
+    /// its own window again
+    pub fn end_pixels(&mut self) {
+        self.pixel_run = false;
     }
 
     #[cfg(feature = "buffered")]
-    /// Turn a pixel on or off. A non-zero `value` is treated as on, `0` as off. If the X and Y
-    /// coordinates are out of the bounds of the display, this method call is a noop.
+    /// Set the framebuffer pixel at `x`, `y` to the RGB565 `color`. If the X and Y coordinates
+    /// are out of the bounds of the display, this method call is a noop.
     pub fn set_pixel(&mut self, x: u32, y: u32, color: u16) {
-        // set bytes in buffer
-        self.buffer[(y as usize * 128usize + x as usize) * 2] = (color >> 8) as u8;
-        self.buffer[((y as usize * 128usize + x as usize) * 2) + 1usize] = color as u8;
+        let (display_width, display_height) = self.display.get_dimensions();
+        if x >= display_width as u32
+            || y >= display_height as u32
+            || self.is_clipped(x, y)
+            || !self.has_buffer()
+        {
+            return;
+        }
+        // set bytes in buffer, which is laid out in the current orientation of the display
+        let i = (y as usize * display_width as usize + x as usize) * BYTES_PER_PIXEL;
+        self.buffer[i..i + BYTES_PER_PIXEL]
+            .copy_from_slice(&encode_pixel_with(color, self.byte_order));
+        self.mark_rows_dirty(y, 1);
     }
 
     #[cfg(feature = "buffered")]
-    pub fn flush(&mut self) {
-        let (display_width, display_height) = self.display.get_size().dimensions();
-        self.display
-            .set_draw_area((0, 0), (display_width, display_height))
-            .unwrap();
-        self.display.draw(self.buffer).unwrap();
+    /// Mark `rows` framebuffer rows starting at `y` as changed, so that the next
+    /// [`flush_dirty_rows`](Self::flush_dirty_rows) sends them. Drawing through the driver does
+    /// this by itself, it is only needed after writing to the framebuffer directly.
+    pub fn mark_rows_dirty(&mut self, y: u32, rows: u32) {
+        self.dirty_rows |= row_mask(y, rows);
     }
 
-    /// Display is set up in column mode, i.e. a byte walks down a column of 8 pixels from
-    /// column 0 on the left, to column _n_ on the right
-    pub fn init(&mut self) -> Result<(), DisplayError> {
-        self.display.init()?;
+    #[cfg(feature = "buffered")]
+    /// Flush the full width of every framebuffer row changed since it was last flushed, e.g. the
+    /// lines of text that got updated. Consecutive rows go out through a single window like
+    /// [`flush_area`](Self::flush_area). Changes are tracked per row of the current orientation,
+    /// `flush` and `flush_continuous` mark every row as sent.
+    pub fn flush_dirty_rows(&mut self) -> Result<(), Error> {
+        let (display_width, display_height) = self.display.get_dimensions();
+        self.dirty_rows &= row_mask(0, display_height as u32);
+        while self.dirty_rows != 0 {
+            let y = self.dirty_rows.trailing_zeros();
+            let rows = (self.dirty_rows >> y).trailing_ones();
+            self.flush_area(Region {
+                x: 0,
+                y: y as u8,
+                width: display_width,
+                height: rows as u8,
+            })?;
+            self.dirty_rows &= !row_mask(y, rows);
+        }
         Ok(())
     }
 
-    /// Set the display rotation
-    pub fn set_rotation(&mut self, rot: DisplayRotation) -> Result<(), DisplayError> {
-        self.display.set_rotation(rot)
-    }
+    #[cfg(not(feature = "buffered"))]
+    /// Set a batch of `(x, y, color)` pixels. Consecutive pixels that sit next to each other on
+    /// the same row are written through a single draw area, so the column, row and `WriteRam`
+    /// commands are only sent once per run instead of once per pixel. Pixels outside of the
+    /// display or the clip rectangle are skipped.
+    pub fn set_pixels(&mut self, pixels: &[(u8, u8, u16)]) -> Result<(), Error> {
+        let (display_width, display_height) = self.display.get_dimensions();
 
-    /// Get display dimensions, taking into account the current rotation of the display
-    pub fn get_dimensions(&self) -> (u8, u8) {
-        self.display.get_dimensions()
+        let mut start = 0;
+        while start < pixels.len() {
+            let (x, y, _) = pixels[start];
+            if self.is_clipped(x as u32, y as u32) {
+                start += 1;
+                continue;
+            }
+
+            // Extend the run as long as the next pixel continues the same row
+            let mut end = start + 1;
+            while end < pixels.len()
+                && pixels[end].1 == y
+                && pixels[end].0 as usize == x as usize + (end - start)
+                && !self.is_clipped(pixels[end].0 as u32, y as u32)
+            {
+                end += 1;
+            }
+            let run = &pixels[start..end];
+            start = end;
+
+            if x >= display_width || y >= display_height {
+                continue;
+            }
+            let run = &run[..run.len().min((display_width - x) as usize)];
+            let ex = x + run.len() as u8;
+
+            self.display.set_draw_area_logical((x, y), (ex, y + 1))?;
+            self.draw_colors(run.iter().map(|&(_, _, color)| color))?;
+        }
+
+        Ok(())
     }
-}
 
-#[cfg(feature = "graphics")]
-extern crate embedded_graphics_core;
-#[cfg(feature = "graphics")]
-use self::embedded_graphics_core::pixelcolor::{raw::RawU16, Rgb565};
-#[cfg(feature = "graphics")]
-use self::embedded_graphics_core::prelude::{
-    Dimensions, DrawTarget, OriginDimensions, Pixel, RawData, Size,
-};
-#[cfg(all(feature = "graphics", not(feature = "buffered")))]
-use self::embedded_graphics_core::{prelude::PointsIter, primitives::Rectangle};
+    #[cfg(all(feature = "graphics", not(feature = "buffered")))]
+    /// Fill the visible part of `area` with `color`, staging the pixels in `scratch` instead of a
+    /// buffer on the stack. Every transfer sends as many whole pixels as fit into `scratch`, so
+    /// its size trades RAM for the number of transfers. The clip rectangle is honoured.
+    ///
+    /// Returns `Error::BufferSize` if `scratch` cannot hold a single pixel.
+    pub fn fill_solid_with_scratch(
+        &mut self,
+        area: &Rectangle,
+        color: Rgb565,
+        scratch: &mut [u8],
+    ) -> Result<(), Error> {
+        if scratch.len() < 2 {
+            return Err(Error::BufferSize);
+        }
+        let drawable_area = self.drawable_area(area);
+        if drawable_area.is_zero_sized() {
+            return Ok(());
+        }
 
-#[cfg(feature = "graphics")]
-#[maybe_async::maybe_async(AFIT)]
-impl<DI: WriteOnlyDataCommand> DrawTarget for GraphicsMode<DI> {
-    type Color = Rgb565;
-    type Error = ();
+        self.set_draw_area_rect(&drawable_area)?;
 
-    async fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        let color = RawU16::from(color).into_inner().to_be_bytes();
+        let len = scratch.len() / 2 * 2;
+        let scratch = &mut scratch[..len];
+        for pixel in scratch.chunks_exact_mut(2) {
+            pixel.copy_from_slice(&color);
+        }
+        let mut remaining =
+            drawable_area.size.width as usize * drawable_area.size.height as usize * 2;
+        while remaining > 0 {
+            let len = remaining.min(scratch.len());
+            self.display.draw(&scratch[..len])?;
+            remaining -= len;
+        }
+        Ok(())
+    }
+
+    #[cfg(all(feature = "graphics", not(feature = "buffered")))]
+    /// Sort a batch of `(x, y, color)` pixels by row and column and hand it to `set_pixels`, which
+    /// sends every horizontal run through a single window. Of a pixel drawn more than once only
+    /// the last colour is kept.
+    fn draw_batch(&mut self, batch: &mut [(u8, u8, u16)]) -> Result<(), Error> {
+        // Insertion sort, as it is stable, works in place and is close to linear on the row by row
+        // output of fonts and most primitives
+        for i in 1..batch.len() {
+            let mut j = i;
+            while j > 0 && (batch[j - 1].1, batch[j - 1].0) > (batch[j].1, batch[j].0) {
+                batch.swap(j - 1, j);
+                j -= 1;
+            }
+        }
+        let mut len = 0;
+        for i in 0..batch.len() {
+            if len > 0 && batch[len - 1].0 == batch[i].0 && batch[len - 1].1 == batch[i].1 {
+                batch[len - 1] = batch[i];
+            } else {
+                batch[len] = batch[i];
+                len += 1;
+            }
+        }
+        self.set_pixels(&batch[..len])
+    }
+
+    #[cfg(not(feature = "buffered"))]
+    /// Send `colors` at the current RAM position, packed into chunks rather than one transfer per
+    /// pixel
+    fn draw_colors<I>(&mut self, colors: I) -> Result<(), Error>
     where
-        I: IntoIterator<Item = Pixel<Self::Color>>,
+        I: Iterator<Item = u16>,
     {
-        let bb = self.bounding_box();
-
-        pixels
-            .into_iter()
-            .filter(|Pixel(pos, _)| bb.contains(*pos))
-            .for_each(|Pixel(pos, color)| {
-                self.set_pixel(pos.x as u32, pos.y as u32, RawU16::from(color).into_inner())
-            });
+        let mut chunk = [0u8; 256];
+        let mut len = 0;
+        for color in colors {
+            chunk[len..len + 2].copy_from_slice(&color.to_be_bytes());
+            len += 2;
+            if len == chunk.len() {
+                self.display.draw(&chunk)?;
+                len = 0;
+            }
+        }
+        if len > 0 {
+            self.display.draw(&chunk[..len])?;
+        }
+        Ok(())
+    }
 
+    #[cfg(feature = "buffered")]
+    /// Set a batch of `(x, y, color)` pixels in the framebuffer. Pixels outside of the display or
+    /// the clip rectangle are skipped.
+    pub fn set_pixels(&mut self, pixels: &[(u8, u8, u16)]) -> Result<(), Error> {
+        let (display_width, display_height) = self.display.get_dimensions();
+        for &(x, y, color) in pixels {
+            if x < display_width && y < display_height {
+                self.set_pixel(x as u32, y as u32, color);
+            }
+        }
         Ok(())
     }
 
+    #[cfg(feature = "graphics")]
+    /// Whether the pixel at `x`, `y` falls outside of the clip rectangle
+    fn is_clipped(&self, x: u32, y: u32) -> bool {
+        self.clip
+            .is_some_and(|clip| !clip.contains(Point::new(x as i32, y as i32)))
+    }
+
+    #[cfg(not(feature = "graphics"))]
+    /// Whether the pixel at `x`, `y` falls outside of the clip rectangle
+    fn is_clipped(&self, _x: u32, _y: u32) -> bool {
+        false
+    }
+
+    /// Part of the row `y` from `x0` to `x1` inclusive that is on the display and within the clip
+    /// rectangle, `None` if nothing is left
+    fn clip_span(&self, x0: i32, x1: i32, y: i32) -> Option<(u8, u8, u8)> {
+        let (display_width, display_height) = self.display.get_dimensions();
+        let (x0, x1) = (x0.max(0), x1.min(display_width as i32 - 1));
+        #[cfg(feature = "graphics")]
+        let (x0, x1) = match &self.clip {
+            Some(clip) if y < clip.top_left.y || y >= clip.top_left.y + clip.size.height as i32 => {
+                return None
+            }
+            Some(clip) => (
+                x0.max(clip.top_left.x),
+                x1.min(clip.top_left.x + clip.size.width as i32 - 1),
+            ),
+            None => (x0, x1),
+        };
+        if y < 0 || y >= display_height as i32 || x0 > x1 {
+            return None;
+        }
+        Some((x0 as u8, x1 as u8, y as u8))
+    }
+
     #[cfg(not(feature = "buffered"))]
-    async fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    /// Fill the row `y` from `x0` to `x1` inclusive through a single window
+    fn fill_span(&mut self, x0: i32, x1: i32, y: i32, color: u16) -> Result<(), Error> {
+        let Some((x0, x1, y)) = self.clip_span(x0, x1, y) else {
+            return Ok(());
+        };
+        self.display
+            .set_draw_area_logical((x0, y), (x1 + 1, y + 1))?;
+        self.draw_colors(core::iter::repeat_n(color, (x1 - x0) as usize + 1))
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Fill the row `y` from `x0` to `x1` inclusive in the framebuffer
+    fn fill_span(&mut self, x0: i32, x1: i32, y: i32, color: u16) -> Result<(), Error> {
+        let Some((x0, x1, y)) = self.clip_span(x0, x1, y) else {
+            return Ok(());
+        };
+        for x in x0..=x1 {
+            self.set_pixel(x as u32, y as u32, color);
+        }
+        Ok(())
+    }
+
+    /// Draw the outline of a circle of `radius` pixels around `cx`, `cy` with the midpoint
+    /// algorithm, without needing embedded-graphics. The flat parts at the top and bottom go out
+    /// as horizontal runs through one window each. The circle is clipped to the display and the
+    /// clip rectangle, a centre off the display is fine.
+    pub fn draw_circle(&mut self, cx: i32, cy: i32, radius: u16, color: u16) -> Result<(), Error> {
+        let (mut x, mut y, mut error) = (0, radius as i32, 1 - radius as i32);
+        while x <= y {
+            // Walk along the row until the circle steps down to the next one
+            let (start, row) = (x, y);
+            while x <= y && y == row {
+                if error < 0 {
+                    error += 2 * x + 3;
+                } else {
+                    error += 2 * (x - y) + 5;
+                    y -= 1;
+                }
+                x += 1;
+            }
+            let end = x - 1;
+
+            // The octants next to the top and bottom run along rows, the others along columns
+            for py in [cy - row, cy + row] {
+                if start == 0 {
+                    self.fill_span(cx - end, cx + end, py, color)?;
+                } else {
+                    self.fill_span(cx - end, cx - start, py, color)?;
+                    self.fill_span(cx + start, cx + end, py, color)?;
+                }
+            }
+            for i in start..=end {
+                for px in [cx - row, cx + row] {
+                    self.fill_span(px, px, cy + i, color)?;
+                    if i > 0 {
+                        self.fill_span(px, px, cy - i, color)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Fill a circle of `radius` pixels around `cx`, `cy`, without needing embedded-graphics.
+    /// Every row of the circle goes out as a single run, clipped to the display and the clip
+    /// rectangle, so a circle completely off the display sends nothing.
+    pub fn fill_circle(&mut self, cx: i32, cy: i32, radius: u16, color: u16) -> Result<(), Error> {
+        let r = radius as i64;
+        // Same pixels as the midpoint algorithm: inside if x² + y² <= r² + r
+        let limit = r * r + r;
+        let mut half = r;
+        for dy in 0..=r {
+            while half > 0 && half * half + dy * dy > limit {
+                half -= 1;
+            }
+            let (x0, x1) = (cx - half as i32, cx + half as i32);
+            self.fill_span(x0, x1, cy + dy as i32, color)?;
+            if dy > 0 {
+                self.fill_span(x0, x1, cy - dy as i32, color)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Draw `text` with its top left corner at `x`, `y` using the bundled 6x8 pixel font, scaled
+    /// up `scale` times. Only the glyph pixels are drawn, the background is left as is. Characters
+    /// outside of printable ASCII are skipped without leaving a gap, and text running off the
+    /// display is clipped.
+    pub fn draw_text(
+        &mut self,
+        x: u8,
+        y: u8,
+        text: &str,
+        color: u16,
+        scale: u8,
+    ) -> Result<(), Error> {
+        let scale = scale.max(1) as u16;
+        let mut pixels = [(0u8, 0u8, 0u16); 32];
+        let mut len = 0;
+
+        let glyphs = text.chars().filter_map(font::glyph);
+        for (i, columns) in glyphs.enumerate() {
+            let cell_x = x as u16 + i as u16 * CHAR_WIDTH as u16 * scale;
+            for row in 0..CHAR_HEIGHT as u16 * scale {
+                let py = y as u16 + row;
+                for column in 0..columns.len() as u16 * scale {
+                    let px = cell_x + column;
+                    let bit = columns[(column / scale) as usize] >> (row / scale) & 1;
+                    if bit == 0 || px > u8::MAX as u16 || py > u8::MAX as u16 {
+                        continue;
+                    }
+                    pixels[len] = (px as u8, py as u8, color);
+                    len += 1;
+                    if len == pixels.len() {
+                        self.set_pixels(&pixels)?;
+                        len = 0;
+                    }
+                }
+            }
+        }
+        self.set_pixels(&pixels[..len])
+    }
+
+    #[cfg(feature = "buffered")]
+    pub fn flush(&mut self) -> Result<(), Error> {
+        if !self.has_buffer() {
+            return Err(Error::BufferSize);
+        }
+        self.set_frame_window()?;
+        self.send_buffer()?;
+        self.continuous_window = true;
+        self.dirty_rows = 0;
+        Ok(())
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Flush the framebuffer like `flush` in bands of 16 rows, awaiting the future returned by
+    /// `yield_fn` between two bands, e.g. `embassy_futures::yield_now`. On a cooperative executor
+    /// this lets other tasks run while a frame is sent over a slow bus. Every band programs its
+    /// own window, which costs a few commands more than `flush`. Returns `Error::BufferSize`
+    /// without a framebuffer.
+    pub async fn flush_yielding<Y, F>(&mut self, mut yield_fn: Y) -> Result<(), Error>
     where
-        I: IntoIterator<Item = Self::Color>,
+        Y: FnMut() -> F,
+        F: core::future::Future<Output = ()>,
     {
-        let drawable_area = area.intersection(&self.bounding_box());
+        let bounds = self.display.display_bounds();
+        let mut y = 0;
+        while y < bounds.height {
+            if y > 0 {
+                yield_fn().await;
+            }
+            let height = FLUSH_YIELD_ROWS.min(bounds.height - y);
+            self.flush_area(Region {
+                y,
+                height,
+                ..bounds
+            })?;
+            y += height;
+        }
+        self.dirty_rows = 0;
+        Ok(())
+    }
 
-        let rot = self.display.get_rotation();
-        let sx = drawable_area.top_left.x as u8;
-        let sy = drawable_area.top_left.y as u8;
-        let ex = (drawable_area.top_left.x as u32 + drawable_area.size.width) as u8;
-        let ey = (drawable_area.top_left.y as u32 + drawable_area.size.height) as u8;
+    #[cfg(feature = "buffered")]
+    /// Flush the framebuffer for streaming full frames, e.g. video or animations. The full screen
+    /// window is only programmed on the first call, following calls just issue `WriteRam` and
+    /// send the frame.
+    ///
+    /// This relies on the display wrapping its RAM position back to the start of the window after
+    /// every full frame. Anything moving the window or sending a partial frame in between breaks
+    /// that assumption: `flush_rows` resets it automatically, after using the [`Display`] directly
+    /// call [`reset_continuous`](Self::reset_continuous) to re-program the window on the next call.
+    pub fn flush_continuous(&mut self) -> Result<(), Error> {
+        if !self.has_buffer() {
+            return Err(Error::BufferSize);
+        }
+        if self.continuous_window {
+            self.display.write_ram()?;
+        } else {
+            self.set_frame_window()?;
+            self.continuous_window = true;
+        }
+        self.send_buffer()?;
+        self.dirty_rows = 0;
+        Ok(())
+    }
 
-        // Set the draw area to the size of the rectangle
-        let (area_start, area_end) = match rot {
-            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => ((sx, sy), (ex, ey)),
-            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => ((sy, sx), (ey, ex)),
+    #[cfg(feature = "buffered")]
+    /// Flush the framebuffer, then hand back the display and the buffer. Use this when tearing
+    /// down or switching modes with a frame still pending, `release` drops whatever has not been
+    /// flushed yet. On error the mode is consumed all the same, as a failed transfer leaves the
+    /// interface in an unknown state.
+    pub fn flush_and_release(mut self) -> Result<(Display<DI>, &'a mut [u8]), Error> {
+        if !self.has_buffer() {
+            return Err(Error::BufferSize);
+        }
+        self.set_frame_window()?;
+        self.send_buffer()?;
+        Ok(self.release())
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Flush the part of the framebuffer covered by `area`, given in the current orientation. The
+    /// area is clamped to the display. Its window is programmed once and the rows are streamed
+    /// into it back to back, the display moves on to the next row by itself.
+    pub fn flush_area(&mut self, area: Region) -> Result<(), Error> {
+        if !self.has_buffer() {
+            return Err(Error::BufferSize);
+        }
+        let (display_width, display_height) = self.display.get_dimensions();
+        let x = area.x.min(display_width);
+        let y = area.y.min(display_height);
+        let width = area.width.min(display_width - x) as usize;
+        let height = area.height.min(display_height - y);
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+        self.continuous_window = false;
+
+        let sx = if self.flip_horizontal {
+            display_width - x - width as u8
+        } else {
+            x
         };
+        let (ex, ey) = (sx + width as u8, y + height);
+        self.display.set_draw_area_logical((sx, y), (ex, ey))?;
+
+        let mut row_bytes = [0u8; 256];
+        for row in y as usize..ey as usize {
+            let first = row * display_width as usize + x as usize;
+            let pixels = &self.buffer[first * BYTES_PER_PIXEL..(first + width) * BYTES_PER_PIXEL];
+            for (i, bytes) in row_bytes[..width * 2].chunks_exact_mut(2).enumerate() {
+                let src = if self.flip_horizontal {
+                    width - 1 - i
+                } else {
+                    i
+                };
+                let pixel = &pixels[src * BYTES_PER_PIXEL..(src + 1) * BYTES_PER_PIXEL];
+                bytes.copy_from_slice(&self.output_pixel(pixel).to_be_bytes());
+            }
+            self.display.draw(&row_bytes[..width * 2])?;
+        }
+        Ok(())
+    }
 
-        self.display.set_draw_area(area_start, area_end).unwrap();
+    #[cfg(feature = "buffered")]
+    /// Send the single framebuffer pixel at `x`, `y` through a 1x1 window, i.e. the window setup
+    /// and exactly two bytes of data. This suits interactive updates that change one pixel at a
+    /// time, e.g. right after a `set_pixel`.
+    ///
+    /// Returns `Error::OutOfBounds` if the pixel is outside of the display.
+    pub fn flush_pixel(&mut self, x: u8, y: u8) -> Result<(), Error> {
+        let (display_width, display_height) = self.display.get_dimensions();
+        if x >= display_width || y >= display_height {
+            return Err(Error::OutOfBounds);
+        }
+        if !self.has_buffer() {
+            return Err(Error::BufferSize);
+        }
+        self.continuous_window = false;
 
-        // Get an iterator of colours as u16
-        // Check points for containment
-        area.points()
-            .zip(colors)
-            .filter(|(pos, _)| drawable_area.contains(*pos))
-            .map(|(_, color)| RawU16::from(color).into_inner())
-            .for_each(|color| {
-                self.display
-                    .draw(&[(color >> 8) as u8, color as u8])
-                    .unwrap()
-            });
+        let i = (y as usize * display_width as usize + x as usize) * BYTES_PER_PIXEL;
+        let color = self.output_pixel(&self.buffer[i..i + BYTES_PER_PIXEL]);
+        let sx = if self.flip_horizontal {
+            display_width - 1 - x
+        } else {
+            x
+        };
+        self.display
+            .set_draw_area_logical((sx, y), (sx + 1, y + 1))?;
+        self.display.draw(&color.to_be_bytes())
+    }
 
+    #[cfg(feature = "buffered")]
+    /// Flush the framebuffer with every colour inverted, e.g. to flash an alert. The inversion is
+    /// applied on the way out, unlike `Command::Invert` it does not stick and the framebuffer is
+    /// left untouched, so the next `flush` shows the normal image again.
+    pub fn flush_inverted(&mut self) -> Result<(), Error> {
+        if !self.has_buffer() {
+            return Err(Error::BufferSize);
+        }
+        self.set_frame_window()?;
+        self.send_buffer_converted(0xFFFF)?;
+        self.continuous_window = true;
         Ok(())
     }
-}
 
-impl<DI: WriteOnlyDataCommand> OriginDimensions for GraphicsMode<DI> {
-    fn size(&self) -> Size {
-        let dim = self.display.get_size().dimensions();
-        Size::from((dim.0 as u32, dim.1 as u32))
+    #[cfg(feature = "buffered")]
+    /// Flush the framebuffer darkened to a quarter of its brightness, e.g. to push the background
+    /// back behind a modal dialog. Like `flush_inverted` the darkening is applied on the way out
+    /// through a tint, on top of one set with `set_tint`, and the framebuffer is left untouched.
+    /// Draw the dialog straight to the display (e.g. through `flush_rows` or `begin_stream`) to
+    /// keep the framebuffer as the background, [`unblank`](Self::unblank) brings it back.
+    pub fn blank(&mut self) -> Result<(), Error> {
+        if !self.has_buffer() {
+            return Err(Error::BufferSize);
+        }
+        let tint = self.tint;
+        let (r, g, b) = tint.unwrap_or((u8::MAX, u8::MAX, u8::MAX));
+        let dim = |scale: u8| (scale as u16 * BLANK_SCALE as u16 / u8::MAX as u16) as u8;
+        self.tint = Some((dim(r), dim(g), dim(b)));
+
+        let flushed = match self.set_frame_window() {
+            Ok(()) => self.send_buffer_converted(0),
+            Err(error) => Err(error),
+        };
+        self.tint = tint;
+        flushed?;
+        self.continuous_window = true;
+        Ok(())
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Show the framebuffer as it is again after [`blank`](Self::blank), with a full
+    /// [`flush_continuous`](Self::flush_continuous)
+    pub fn unblank(&mut self) -> Result<(), Error> {
+        self.flush_continuous()
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Flush what every embedded-graphics `DrawTarget` operation changed right after it, so that
+    /// drawings show up without calling `flush`, e.g. while prototyping. Fills send just their
+    /// area, `draw_iter` sends the changed rows like `flush_dirty_rows`. Off by default: every
+    /// draw becomes a transfer of its own, which costs a lot more bus time than one flush per
+    /// frame. The driver's own drawing methods are not affected.
+    pub fn set_auto_flush(&mut self, enable: bool) {
+        self.auto_flush = enable;
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Whether draws are flushed right away, see [`set_auto_flush`](Self::set_auto_flush)
+    pub fn auto_flush(&self) -> bool {
+        self.auto_flush
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Make the next `flush_continuous` re-program the full screen window
+    pub fn reset_continuous(&mut self) {
+        self.continuous_window = false;
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Mirror the content horizontally while flushing, for setups that view the panel through a
+    /// mirror or beam splitter. This only reverses the pixels of every row on their way out, the
+    /// framebuffer, the rotation and the hardware remap are left untouched.
+    ///
+    /// Flipping is off by default as it costs a copy of every pixel and sends the frame one row at
+    /// a time, i.e. one transfer per row instead of one per frame.
+    pub fn set_flip_horizontal(&mut self, flip: bool) {
+        self.flip_horizontal = flip;
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Program the full screen window of the active rows, see [`Display::display_bounds`]
+    fn set_frame_window(&mut self) -> Result<(), Error> {
+        let bounds = self.display.display_bounds();
+        self.display
+            .set_draw_area_logical((0, 0), (bounds.width, bounds.height))?;
+        Ok(())
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Send the part of the framebuffer on the active rows at the current RAM position, i.e. the
+    /// whole framebuffer unless [`set_active_rows`](Self::set_active_rows) left some rows dark
+    fn send_buffer(&mut self) -> Result<(), Error> {
+        if !self.has_buffer() {
+            return Err(Error::BufferSize);
+        }
+        let bounds = self.display.display_bounds();
+        if self.flip_horizontal
+            || self.byte_order != ByteOrder::BigEndian
+            || self.tint.is_some()
+            || self.mono_contrast.is_some()
+            || bounds.width != self.display.get_dimensions().0
+        {
+            return self.send_buffer_converted(0);
+        }
+        // Full width rows, the visible ones are the start of the framebuffer
+        let frame = &self.buffer[..self.display.frame_pixels() * BYTES_PER_PIXEL];
+
+        #[cfg(not(feature = "palette8"))]
+        self.display.draw(frame)?;
+
+        // Expand the palette back to RGB565 one chunk at a time
+        #[cfg(feature = "palette8")]
+        {
+            let mut chunk = [0u8; 256];
+            for pixels in frame.chunks(chunk.len() / 2) {
+                for (bytes, &pixel) in chunk.chunks_exact_mut(2).zip(pixels) {
+                    bytes.copy_from_slice(
+                        &decode_pixel_with([pixel], self.byte_order).to_be_bytes(),
+                    );
+                }
+                self.display.draw(&chunk[..pixels.len() * 2])?;
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Scale the red, green and blue channel of every pixel by `r_scale`, `g_scale` and `b_scale`
+    /// out of 255 while flushing, e.g. to cut down on blue light. Unlike the contrast, which scales
+    /// every channel, this is done in software and leaves the framebuffer untouched. Scaling all
+    /// channels by 255 turns the tint off again.
+    ///
+    /// A tint costs a copy and a conversion of every pixel, and sends the frame one row at a time.
+    pub fn set_tint(&mut self, r_scale: u8, g_scale: u8, b_scale: u8) {
+        self.tint = match (r_scale, g_scale, b_scale) {
+            (u8::MAX, u8::MAX, u8::MAX) => None,
+            scale => Some(scale),
+        };
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Switch to a dim, gray look for always-on faces such as a watch showing the time. While on,
+    /// every pixel is turned into the gray of the same brightness while flushing, which leaves the
+    /// framebuffer untouched and costs a conversion of every pixel like a tint. The master
+    /// contrast drops to 4 as well, which cuts the current through every lit pixel to 5/16 of
+    /// the full current. Most of the power saved comes from this, a gray pixel lights all three
+    /// subpixels and draws about as much as the colour it replaces. Turning mono mode off
+    /// restores the previous master contrast. The next flush, even of only dirty rows, shows the
+    /// change.
+    pub fn mono_mode(&mut self, on: bool) -> Result<(), Error> {
+        match (on, self.mono_contrast) {
+            (true, None) => {
+                let contrast = self.display.config().master_contrast;
+                self.display.set_master_contrast(MONO_CONTRAST)?;
+                self.mono_contrast = Some(contrast);
+            }
+            (false, Some(contrast)) => {
+                self.display.set_master_contrast(contrast)?;
+                self.mono_contrast = None;
+            }
+            _ => return Ok(()),
+        }
+        self.dirty_rows = u128::MAX;
+        Ok(())
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Convert a framebuffer pixel into the colour that is sent to the display
+    fn output_pixel(&self, pixel: &[u8]) -> u16 {
+        let pixel = <[u8; BYTES_PER_PIXEL]>::try_from(pixel).unwrap();
+        let color = decode_pixel_with(pixel, self.byte_order);
+        let color = match self.tint {
+            Some(tint) => tint_pixel(color, tint),
+            None => color,
+        };
+        match self.mono_contrast {
+            Some(_) => gray_pixel(color),
+            None => color,
+        }
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Send the framebuffer like `send_buffer` one row at a time, XORing every pixel with `xor`
+    /// and reversing the rows if the content is flipped
+    fn send_buffer_converted(&mut self, xor: u16) -> Result<(), Error> {
+        let display_width = self.display.get_dimensions().0 as usize;
+        let bounds = self.display.display_bounds();
+        let width = bounds.width as usize;
+        let mut row_bytes = [0u8; 256];
+        let rows = self
+            .buffer
+            .chunks_exact(display_width * BYTES_PER_PIXEL)
+            .take(bounds.height as usize);
+        for row in rows {
+            let out = row_bytes[..width * 2].chunks_exact_mut(2);
+            for (i, bytes) in out.enumerate() {
+                let src = if self.flip_horizontal {
+                    width - 1 - i
+                } else {
+                    i
+                };
+                let color =
+                    self.output_pixel(&row[src * BYTES_PER_PIXEL..(src + 1) * BYTES_PER_PIXEL]);
+                bytes.copy_from_slice(&(color ^ xor).to_be_bytes());
+            }
+            self.display.draw(&row_bytes[..width * 2])?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "stream")]
+    /// Play a stream of frames, e.g. video coming in through a channel, flushing each one as soon
+    /// as it arrives. Every frame has to be a full frame of packed big endian RGB565, i.e. two
+    /// bytes for every pixel of [`display_bounds`](Self::display_bounds). Frames are sent as they
+    /// are and bypass
+    /// the framebuffer (if any). Returns once the stream ends, or with
+    /// `Error::InvalidData` on the first frame of the wrong size.
+    ///
+    /// The full screen window is only programmed for the first frame, the ones after that just
+    /// issue `WriteRam` like [`flush_continuous`](Self::flush_continuous). A frame is borrowed
+    /// while it is sent and dropped right after, so producers recycling a pool of buffers get
+    /// them back through `Drop` of the stream item. There is no queueing on this side: the
+    /// transfer blocks, and the producer can only run ahead as far as its channel allows.
+    pub async fn play_stream<S, F>(&mut self, mut stream: S) -> Result<(), Error>
+    where
+        S: Stream<Item = F> + Unpin,
+        F: AsRef<[u8]>,
+    {
+        #[cfg(feature = "buffered")]
+        {
+            self.continuous_window = false;
+        }
+
+        let (display_width, _) = self.display.get_size().dimensions();
+        let active_rows = self.display.active_rows();
+        while let Some(frame) = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+            let frame = frame.as_ref();
+            if frame.len() != self.display.frame_pixels() * 2 {
+                return Err(Error::InvalidData);
+            }
+            // The draw window cache turns this into a lone `WriteRam` after the first frame
+            self.display
+                .set_draw_area((0, 0), (display_width, active_rows))?;
+            self.display.draw(frame)?;
+        }
+        Ok(())
+    }
+
+    /// Show a full screen splash, e.g. a boot logo compiled into flash, straight from `data`. The
+    /// data is packed big endian RGB565 of the [`display_bounds`](Self::display_bounds) in the
+    /// current orientation, row by row, i.e. exactly two bytes for every pixel. It is sent in one
+    /// go and does not touch the framebuffer (if any).
+    ///
+    /// Such an array can be generated from an image with e.g.
+    /// `ffmpeg -i logo.png -s 128x128 -f rawvideo -pix_fmt rgb565be logo.raw` and pulled in with
+    /// `include_bytes!("logo.raw")`.
+    ///
+    /// Returns `Error::InvalidData` if `data` is not a full frame.
+    pub fn draw_splash(&mut self, data: &'static [u8]) -> Result<(), Error> {
+        if data.len() != self.display.frame_pixels() * 2 {
+            return Err(Error::InvalidData);
+        }
+
+        #[cfg(feature = "buffered")]
+        {
+            self.continuous_window = false;
+        }
+
+        let (display_width, _) = self.display.get_size().dimensions();
+        self.display
+            .set_draw_area((0, 0), (display_width, self.display.active_rows()))?;
+        self.display.draw(data)
+    }
+
+    /// Send `rows` full-width rows of already packed big endian RGB565 `data` to the display,
+    /// starting at `start_row`. The data is sent as is and does not touch the framebuffer (if
+    /// any), which suits renderers that track changed rows themselves.
+    ///
+    /// Returns `Error::InvalidData` if `data` is not exactly `rows * width * 2`
+    /// bytes long, and `Error::OutOfBounds` if the rows extend past the bottom of the
+    /// display.
+    pub fn flush_rows(&mut self, start_row: u8, rows: u8, data: &[u8]) -> Result<(), Error> {
+        let (display_width, display_height) = self.display.get_dimensions();
+        if data.len() != rows as usize * display_width as usize * 2 {
+            return Err(Error::InvalidData);
+        }
+        if start_row as u16 + rows as u16 > display_height as u16 {
+            return Err(Error::OutOfBounds);
+        }
+        if rows == 0 {
+            return Ok(());
+        }
+
+        #[cfg(feature = "buffered")]
+        {
+            self.continuous_window = false;
+        }
+
+        self.display
+            .set_draw_area_logical((0, start_row), (display_width, start_row + rows))?;
+        self.display.draw(data)
+    }
+
+    /// Start streaming data into the area between `start` and the exclusive `end`, given in the
+    /// current orientation of the display. See [`Display::begin_stream`].
+    pub fn begin_stream(
+        &mut self,
+        start: (u8, u8),
+        end: (u8, u8),
+    ) -> Result<StreamGuard<'_, DI>, Error> {
+        #[cfg(feature = "buffered")]
+        {
+            self.continuous_window = false;
+        }
+
+        let (area_start, area_end) = self.display.rotate_area(start, end);
+        self.display.begin_stream(area_start, area_end)
+    }
+
+    /// Display is set up in column mode, i.e. a byte walks down a column of 8 pixels from
+    /// column 0 on the left, to column _n_ on the right
+    pub fn init(&mut self) -> Result<(), Error> {
+        self.display.init()?;
+        Ok(())
+    }
+
+    /// Re-send the configuration without touching the image, see [`Display::reinit_config`]
+    pub fn reinit_config(&mut self) -> Result<(), Error> {
+        self.display.reinit_config()
+    }
+
+    /// Get the configuration that `init` sends, see [`Display::config`]
+    pub fn config(&self) -> &DisplayConfig {
+        self.display.config()
+    }
+
+    /// Get the configuration as last sent to the panel, see [`Display::applied_config`]
+    pub fn applied_config(&self) -> &DisplayConfig {
+        self.display.applied_config()
+    }
+
+    /// Initialise the display without clearing it, see [`Display::init_no_clear`]
+    pub fn init_no_clear(&mut self) -> Result<(), Error> {
+        self.display.init_no_clear()
+    }
+
+    /// Initialise the display and wait for it to settle, see [`Display::init_with_delay`]
+    pub fn init_with_delay<DELAY>(&mut self, delay: &mut DELAY, settle_ms: u32) -> Result<(), Error>
+    where
+        DELAY: DelayNs,
+    {
+        self.display.init_with_delay(delay, settle_ms)
+    }
+
+    /// Initialise the display, calling `feed` along the way, see [`Display::init_chunked`]
+    pub fn init_chunked<F>(&mut self, feed: F) -> Result<(), Error>
+    where
+        F: FnMut(),
+    {
+        self.display.init_chunked(feed)
+    }
+
+    /// Initialise the display, counting and timing each phase, see
+    /// [`Display::init_instrumented`]
+    pub fn init_instrumented<F>(&mut self, now_us: F) -> Result<InitStats, Error>
+    where
+        F: FnMut() -> u32,
+    {
+        self.display.init_instrumented(now_us)
+    }
+
+    /// Run a visual self test of the panel, see [`Display::self_test`]
+    pub fn self_test<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), Error>
+    where
+        DELAY: DelayNs,
+    {
+        self.display.self_test(delay)
+    }
+
+    /// Run the self test followed by a test pattern, see [`Display::self_test_with_pattern`]
+    pub fn self_test_with_pattern<DELAY>(
+        &mut self,
+        delay: &mut DELAY,
+        pattern: TestPattern,
+    ) -> Result<(), Error>
+    where
+        DELAY: DelayNs,
+    {
+        self.display.self_test_with_pattern(delay, pattern)
+    }
+
+    #[cfg(not(feature = "buffered"))]
+    /// Fill the display with a test pattern, see [`Display::draw_test_pattern`]. The clip
+    /// rectangle is ignored.
+    pub fn draw_test_pattern(&mut self, pattern: TestPattern) -> Result<(), Error> {
+        self.display.draw_test_pattern(pattern)
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Fill the framebuffer with a test pattern, see [`TestPattern`]. The clip rectangle is
+    /// ignored. Returns `Error::BufferSize` without a framebuffer, call `flush` to show the
+    /// pattern.
+    pub fn draw_test_pattern(&mut self, pattern: TestPattern) -> Result<(), Error> {
+        if !self.has_buffer() {
+            return Err(Error::BufferSize);
+        }
+        let (width, height) = self.get_dimensions();
+        let order = self.byte_order;
+        for (i, pixel) in self.buffer.chunks_exact_mut(BYTES_PER_PIXEL).enumerate() {
+            let (x, y) = (i % width as usize, i / width as usize);
+            let color = pattern.color(x as u8, y as u8, width, height);
+            pixel.copy_from_slice(&encode_pixel_with(color, order));
+        }
+        self.dirty_rows = u128::MAX;
+        Ok(())
+    }
+
+    /// Access the interface directly, see [`Display::interface_mut`]. The next
+    /// `flush_continuous` programs its window again, in case the RAM position moved.
+    pub fn interface_mut(&mut self) -> &mut DI {
+        #[cfg(feature = "buffered")]
+        {
+            self.continuous_window = false;
+        }
+        self.display.interface_mut()
+    }
+
+    /// Double the frame rate to cut down on flicker when dimmed, see
+    /// [`Display::optimize_for_low_brightness`]
+    pub fn optimize_for_low_brightness(&mut self) -> Result<(), Error> {
+        self.display.optimize_for_low_brightness()
+    }
+
+    /// Restore the frame rate set by `init`, see [`Display::optimize_for_normal`]
+    pub fn optimize_for_normal(&mut self) -> Result<(), Error> {
+        self.display.optimize_for_normal()
+    }
+
+    /// Send a NOP, e.g. as timing padding, see [`Display::send_nop`]
+    pub fn send_nop(&mut self) -> Result<(), Error> {
+        self.display.send_nop()
+    }
+
+    /// Enable or disable the display enhancement mode, see [`Display::set_display_enhancement`]
+    pub fn set_display_enhancement(&mut self, enable: bool) -> Result<(), Error> {
+        self.display.set_display_enhancement(enable)
+    }
+
+    /// Pad every data transfer to a multiple of `alignment` bytes, see
+    /// [`Display::set_data_alignment`]
+    pub fn set_data_alignment(&mut self, alignment: u8) -> Result<(), Error> {
+        self.display.set_data_alignment(alignment)
+    }
+
+    /// Lock or unlock the command interface, see [`Display::set_command_lock`]
+    pub fn set_command_lock(&mut self, mode: CommandLockMode) -> Result<(), Error> {
+        self.display.set_command_lock(mode)
+    }
+
+    /// Set the pre-charge voltage, see [`Display::set_precharge_voltage_level`]
+    pub fn set_precharge_voltage_level(&mut self, level: u8) -> Result<(), Error> {
+        self.display.set_precharge_voltage_level(level)
+    }
+
+    /// Set the second pre-charge period, see [`Display::set_second_precharge`]
+    pub fn set_second_precharge(&mut self, period: u8) -> Result<(), Error> {
+        self.display.set_second_precharge(period)
+    }
+
+    /// Apply a set of contrast and pre-charge settings, see [`Display::apply_profile`]
+    pub fn apply_profile(&mut self, profile: DisplayProfile) -> Result<(), Error> {
+        self.display.apply_profile(profile)
+    }
+
+    /// Get the profile last applied, see [`Display::profile`]
+    pub fn profile(&self) -> Option<DisplayProfile> {
+        self.display.profile()
+    }
+
+    /// Get the most recent error of the interface, see [`Display::last_error`]
+    pub fn last_error(&self) -> Option<DisplayError> {
+        self.display.last_error()
+    }
+
+    /// Forget the most recent error of the interface, see [`Display::clear_last_error`]
+    pub fn clear_last_error(&mut self) {
+        self.display.clear_last_error()
+    }
+
+    /// Set the master contrast, see [`Display::set_master_contrast`]
+    pub fn set_master_contrast(&mut self, contrast: u8) -> Result<(), Error> {
+        self.display.set_master_contrast(contrast)
+    }
+
+    /// Ramp the master contrast to `target_contrast`, see [`Display::fade_to`]
+    pub fn fade_to<DELAY>(
+        &mut self,
+        target_contrast: u8,
+        steps: u16,
+        delay: &mut DELAY,
+        step_ms: u32,
+    ) -> Result<(), Error>
+    where
+        DELAY: DelayNs,
+    {
+        self.display.fade_to(target_contrast, steps, delay, step_ms)
+    }
+
+    /// Upload a custom gray scale table, see [`Display::set_gamma`]
+    pub fn set_gamma(&mut self, table: &GammaTable) -> Result<(), Error> {
+        self.display.set_gamma(table)
+    }
+
+    /// Go back to the built-in linear gray scale table
+    pub fn set_linear_gamma(&mut self) -> Result<(), Error> {
+        self.display.set_linear_gamma()
+    }
+
+    /// Configure the two GPIO pins of the panel, see [`Display::set_gpio`]
+    pub fn set_gpio(&mut self, pin0: GpioMode, pin1: GpioMode) -> Result<(), Error> {
+        self.display.set_gpio(pin0, pin1)
+    }
+
+    /// Set the display rotation
+    pub fn set_rotation(&mut self, rot: DisplayRotation) -> Result<(), Error> {
+        self.display.set_rotation(rot)
+    }
+
+    /// Get the RAM address the next pixel lands on, see [`Display::current_position`]
+    pub fn current_position(&self) -> Option<(u8, u8)> {
+        self.display.current_position()
+    }
+
+    /// Whether the last draw window was filled exactly, see [`Display::window_filled`]
+    pub fn window_filled(&self) -> bool {
+        self.display.window_filled()
+    }
+
+    /// Re-establish the full screen window after it got out of step, see [`Display::resync`]
+    pub fn resync(&mut self) -> Result<(), Error> {
+        #[cfg(feature = "buffered")]
+        {
+            self.continuous_window = false;
+        }
+        self.display.resync()
+    }
+
+    /// Get display dimensions, taking into account the current rotation of the display
+    pub fn get_dimensions(&self) -> (u8, u8) {
+        self.display.get_dimensions()
+    }
+
+    /// Number of bytes sent for a full frame, see [`Display::frame_size_bytes`]
+    pub fn frame_size_bytes(&self) -> usize {
+        self.display.frame_size_bytes()
+    }
+
+    /// Optional controller features supported by the panel, see [`DisplaySize::capabilities`]
+    ///
+    /// [`DisplaySize::capabilities`]: crate::properties::DisplaySize::capabilities
+    pub fn capabilities(&self) -> Capabilities {
+        self.display.capabilities()
+    }
+
+    /// Get the visible extent of the display, taking into account the current rotation and the
+    /// active rows
+    pub fn display_bounds(&self) -> Region {
+        self.display.display_bounds()
+    }
+
+    /// Only drive the top `rows` rows of the display RAM, see [`Display::set_active_rows`].
+    /// Drawing through embedded-graphics is clipped to the rows left, and `flush` only sends
+    /// them.
+    pub fn set_active_rows(&mut self, rows: u8) -> Result<(), Error> {
+        self.display.set_active_rows(rows)
+    }
+
+    /// Get the number of rows of the display RAM that are driven
+    pub fn active_rows(&self) -> u8 {
+        self.display.active_rows()
+    }
+
+    /// Switch the colour depth of the display RAM, see [`Display::set_color_mode`]. The
+    /// framebuffer keeps its layout, redraw or `flush` after switching.
+    pub fn set_color_mode(&mut self, mode: ColorMode) -> Result<(), Error> {
+        self.display.set_color_mode(mode)
+    }
+
+    /// Get the colour depth of the display RAM
+    pub fn color_mode(&self) -> ColorMode {
+        self.display.color_mode()
+    }
+}
+
+#[cfg(feature = "graphics")]
+use crate::display::DrawArea;
+#[cfg(feature = "graphics")]
+extern crate embedded_graphics_core;
+#[cfg(feature = "graphics")]
+use self::embedded_graphics_core::pixelcolor::{raw::RawU16, Rgb565, Rgb888};
+#[cfg(feature = "graphics")]
+use self::embedded_graphics_core::prelude::{
+    Dimensions, DrawTarget, OriginDimensions, Pixel, Point, RawData, RgbColor, Size,
+};
+#[cfg(feature = "graphics")]
+use self::embedded_graphics_core::{prelude::PointsIter, primitives::Rectangle};
+#[cfg(feature = "embedded-graphics")]
+use crate::properties::RawByteOrder;
+#[cfg(feature = "embedded-graphics")]
+use embedded_graphics::framebuffer::Framebuffer;
+
+#[cfg(feature = "graphics")]
+#[maybe_async::maybe_async(AFIT)]
+impl<DI: WriteOnlyDataCommand> DrawTarget for GraphicsMode<'_, DI> {
+    type Color = Rgb565;
+    type Error = Error;
+
+    #[cfg(not(feature = "buffered"))]
+    async fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let bb = self.bounding_box();
+
+        // Without a framebuffer every window costs three commands, so pixels are gathered and sent
+        // as horizontal runs instead of one at a time
+        let mut batch = [(0u8, 0u8, 0u16); DRAW_ITER_BATCH];
+        let mut len = 0;
+        for Pixel(pos, color) in pixels.into_iter().filter(|Pixel(pos, _)| bb.contains(*pos)) {
+            batch[len] = (pos.x as u8, pos.y as u8, RawU16::from(color).into_inner());
+            len += 1;
+            if len == batch.len() {
+                self.draw_batch(&mut batch)?;
+                len = 0;
+            }
+        }
+        self.draw_batch(&mut batch[..len])
+    }
+
+    #[cfg(feature = "buffered")]
+    async fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let bb = self.bounding_box();
+
+        pixels
+            .into_iter()
+            .filter(|Pixel(pos, _)| bb.contains(*pos))
+            .for_each(|Pixel(pos, color)| {
+                self.set_pixel(pos.x as u32, pos.y as u32, RawU16::from(color).into_inner())
+            });
+        if self.auto_flush && self.has_buffer() {
+            self.flush_dirty_rows()?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "buffered"))]
+    async fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        let drawable_area = self.drawable_area(area);
+        if drawable_area.is_zero_sized() {
+            return Ok(());
+        }
+
+        // Set the draw area to the size of the rectangle
+        self.set_draw_area_rect(&drawable_area)?;
+
+        let colors = colors
+            .into_iter()
+            .map(|color| RawU16::from(color).into_inner());
+        if drawable_area == *area {
+            // Fully visible, every colour goes to the display in order
+            let pixels = area.size.width as usize * area.size.height as usize;
+            self.draw_colors(colors.take(pixels))?;
+        } else {
+            // Check points for containment
+            let visible = area
+                .points()
+                .zip(colors)
+                .filter(|(pos, _)| drawable_area.contains(*pos))
+                .map(|(_, color)| color);
+            self.draw_colors(visible)?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "buffered")]
+    async fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        let drawable_area = self.drawable_area(area);
+        if drawable_area.is_zero_sized() || !self.has_buffer() {
+            return Ok(());
+        }
+        let dirty_rows = self.dirty_rows;
+        self.mark_rows_dirty(drawable_area.top_left.y as u32, drawable_area.size.height);
+
+        // The framebuffer is laid out in the current orientation, the rotation is applied by the
+        // display when the buffer is flushed
+        let display_width = self.display.get_dimensions().0 as usize;
+        if drawable_area == *area {
+            // Fully visible, fill row by row without checking every point
+            let width = area.size.width as usize;
+            let order = self.byte_order;
+            let mut colors = colors.into_iter();
+            for y in area.rows() {
+                let start =
+                    (y as usize * display_width + area.top_left.x as usize) * BYTES_PER_PIXEL;
+                let row = &mut self.buffer[start..start + width * BYTES_PER_PIXEL];
+                for (pixel, color) in row.chunks_exact_mut(BYTES_PER_PIXEL).zip(&mut colors) {
+                    pixel.copy_from_slice(&encode_pixel_with(
+                        RawU16::from(color).into_inner(),
+                        order,
+                    ));
+                }
+            }
+        } else {
+            let order = self.byte_order;
+            area.points()
+                .zip(colors)
+                .filter(|(pos, _)| drawable_area.contains(*pos))
+                .for_each(|(pos, color)| {
+                    let i = (pos.y as usize * display_width + pos.x as usize) * BYTES_PER_PIXEL;
+                    self.buffer[i..i + BYTES_PER_PIXEL].copy_from_slice(&encode_pixel_with(
+                        RawU16::from(color).into_inner(),
+                        order,
+                    ));
+                });
+        }
+
+        if self.auto_flush {
+            // Only the area went out, rows that were dirty before stay dirty
+            self.flush_area(Region {
+                x: drawable_area.top_left.x as u8,
+                y: drawable_area.top_left.y as u8,
+                width: drawable_area.size.width as u8,
+                height: drawable_area.size.height as u8,
+            })?;
+            self.dirty_rows = dirty_rows;
+        }
+        Ok(())
+    }
+
+    async fn fill_solid(
+        &mut self,
+        area: &Rectangle,
+        color: Self::Color,
+    ) -> Result<(), Self::Error> {
+        // Blacking out the whole screen, e.g. through `DrawTarget::clear`, takes the bulk clear
+        // path instead of going pixel by pixel
+        if color == Rgb565::BLACK && *area == self.bounding_box() && self.clip.is_none() {
+            #[cfg(not(feature = "buffered"))]
+            return self.clear();
+            #[cfg(feature = "buffered")]
+            return self.clear(self.auto_flush);
+        }
+        self.fill_contiguous(area, core::iter::repeat(color)).await
+    }
+
+    async fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        // The whole screen goes out in bulk, through the zeroes of `clear` for black and
+        // `clear_to` for any other colour. A clip rectangle limits it to a plain fill.
+        if self.clip.is_some() {
+            return self.fill_solid(&self.bounding_box(), color).await;
+        }
+        if color == Rgb565::BLACK {
+            #[cfg(not(feature = "buffered"))]
+            self.clear()?;
+            #[cfg(feature = "buffered")]
+            self.clear(self.auto_flush)?;
+        } else {
+            self.clear_to(RawU16::from(color).into_inner())?;
+            #[cfg(feature = "buffered")]
+            if self.auto_flush {
+                self.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<DI: WriteOnlyDataCommand> OriginDimensions for GraphicsMode<'_, DI> {
+    fn size(&self) -> Size {
+        let bounds = self.display.display_bounds();
+        Size::from((bounds.width as u32, bounds.height as u32))
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<DI: WriteOnlyDataCommand> GraphicsMode<'_, DI> {
+    /// Restrict all following drawing through `set_pixel`, `set_pixels`, the embedded-graphics
+    /// `DrawTarget` and the other drawing methods of this mode to `area`, pixels outside of it are
+    /// dropped. Unlike `.clipped()` the clip stays in place until it is replaced, `None` restores
+    /// drawing to the full screen.
+    pub fn set_clip(&mut self, area: Option<Rectangle>) {
+        self.clip = area;
+    }
+
+    /// Part of `area` that is on the display and within the clip rectangle
+    fn drawable_area(&self, area: &Rectangle) -> Rectangle {
+        let area = area.intersection(&self.bounding_box());
+        match &self.clip {
+            Some(clip) => area.intersection(clip),
+            None => area,
+        }
+    }
+
+    /// Get the current clip rectangle, see [`set_clip`](Self::set_clip)
+    pub fn clip(&self) -> Option<Rectangle> {
+        self.clip
+    }
+
+    /// Enable or disable a 4x4 ordered dither on colours of more than 16 bits, i.e. those of
+    /// [`fill_with_rgb888`](Self::fill_with_rgb888), which hides the banding of RGB565 on smooth
+    /// gradients. Off by default, as it adds a fine pattern to flat colours that do not fall on
+    /// an RGB565 value. RGB565 content is always drawn as it is.
+    pub fn set_dither(&mut self, enable: bool) {
+        self.dither = enable;
+    }
+
+    /// Whether colours are dithered, see [`set_dither`](Self::set_dither)
+    pub fn dither(&self) -> bool {
+        self.dither
+    }
+
+    /// Set the draw area of the display to `area`, given in the current orientation. The area is
+    /// clamped to the display and swapped into display coordinates for 90º and 270º rotations
+    /// before it is passed on to [`Display::set_draw_area`], whose programmed window is returned.
+    ///
+    /// Returns `Error::OutOfBounds` if no part of `area` is on the display.
+    pub fn set_draw_area_rect(&mut self, area: &Rectangle) -> Result<DrawArea, Error> {
+        let area = area.intersection(&self.bounding_box());
+        if area.is_zero_sized() {
+            return Err(Error::OutOfBounds);
+        }
+
+        let sx = area.top_left.x as u8;
+        let sy = area.top_left.y as u8;
+        let ex = (area.top_left.x as u32 + area.size.width) as u8;
+        let ey = (area.top_left.y as u32 + area.size.height) as u8;
+        self.display.set_draw_area_logical((sx, sy), (ex, ey))
+    }
+
+    /// Get the visible extent of the display as a `Rectangle`, taking into account the current
+    /// rotation and the active rows
+    pub fn display_bounds_rect(&self) -> Rectangle {
+        Rectangle::new(Point::zero(), self.size())
+    }
+
+    /// Get the size of the panel as given by its
+    /// [`DisplaySize`](crate::properties::DisplaySize), e.g. 128x96, regardless of the rotation
+    /// and the active rows. Unlike `size`, which is what drawing happens in, this stays the same
+    /// for a panel mounted in portrait or landscape.
+    pub fn physical_size(&self) -> Size {
+        let (width, height) = self.display.get_size().dimensions();
+        Size::new(width as u32, height as u32)
+    }
+
+    /// Get the chunk size that layers flushing the display in parts, e.g. to compress them,
+    /// should prefer: bands spanning the full width of the display, 8 rows high. Any rectangle
+    /// costs the same column, row and `WriteRam` commands, but only a full width band covers a
+    /// contiguous range of the display RAM in the current orientation and of the framebuffer, so
+    /// it can be sent in one go without gaps. With 8 rows a band is 2 KiB on a 128 pixel wide
+    /// display, and the last band is shorter if the height is not a multiple of that.
+    pub fn preferred_chunk_size(&self) -> Size {
+        Size::new(
+            self.size().width,
+            PREFERRED_CHUNK_ROWS.min(self.size().height),
+        )
+    }
+
+    #[cfg(not(feature = "buffered"))]
+    /// Draw raw RGB565 `data` covering `area`, row by row. The data is big endian unless changed
+    /// through [`set_byte_order`](Self::set_byte_order). This is the same layout as the data of
+    /// an embedded-graphics `ImageRaw<Rgb565>`, so drawing an image with
+    /// `draw_raw(&Rectangle::new(position, image.size()), data)` streams its bytes as they are
+    /// instead of converting each pixel. Parts of the area outside of the display or the clip
+    /// rectangle are dropped.
+    ///
+    /// Returns `Error::InvalidData` if `data` does not hold exactly one pixel for
+    /// every point of `area`.
+    pub fn draw_raw(&mut self, area: &Rectangle, data: &[u8]) -> Result<(), Error> {
+        self.draw_raw_with_order(area, data, self.byte_order)
+    }
+
+    #[cfg(not(feature = "buffered"))]
+    /// Draw raw RGB565 `data` in the byte order `order` instead of the one set through
+    /// [`set_byte_order`](Self::set_byte_order), see [`draw_raw`](Self::draw_raw). The display
+    /// takes big endian data, which is sent as it is, little endian data is swapped on the fly a
+    /// chunk at a time.
+    pub fn draw_raw_with_order(
+        &mut self,
+        area: &Rectangle,
+        data: &[u8],
+        order: ByteOrder,
+    ) -> Result<(), Error> {
+        let width = area.size.width as usize;
+        if data.len() != width * area.size.height as usize * 2 {
+            return Err(Error::InvalidData);
+        }
+        let drawable_area = self.drawable_area(area);
+        if drawable_area.is_zero_sized() {
+            return Ok(());
+        }
+
+        self.set_draw_area_rect(&drawable_area)?;
+
+        // Fully visible, send everything at once
+        let swap = order != ByteOrder::BigEndian;
+        if drawable_area == *area && !swap {
+            return self.display.draw(data);
+        }
+
+        let skip_x = (drawable_area.top_left.x - area.top_left.x) as usize;
+        let skip_y = (drawable_area.top_left.y - area.top_left.y) as usize;
+        for row in data
+            .chunks_exact(width * 2)
+            .skip(skip_y)
+            .take(drawable_area.size.height as usize)
+        {
+            let row = &row[skip_x * 2..(skip_x + drawable_area.size.width as usize) * 2];
+            if swap {
+                self.draw_colors(row.chunks_exact(2).map(|c| order.read([c[0], c[1]])))?;
+            } else {
+                self.display.draw(row)?;
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Draw raw RGB565 `data` covering `area` into the framebuffer, row by row. The data is big
+    /// endian unless changed through [`set_byte_order`](Self::set_byte_order). This is the same
+    /// layout as the data of an embedded-graphics `ImageRaw<Rgb565>`, so drawing an image
+    /// with `draw_raw(&Rectangle::new(position, image.size()), data)` copies its bytes instead of
+    /// converting each pixel. Parts of the area outside of the display or the clip rectangle are
+    /// dropped.
+    ///
+    /// Returns `Error::InvalidData` if `data` does not hold exactly one pixel for
+    /// every point of `area`.
+    pub fn draw_raw(&mut self, area: &Rectangle, data: &[u8]) -> Result<(), Error> {
+        self.draw_raw_with_order(area, data, self.byte_order)
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Draw raw RGB565 `data` in the byte order `order` instead of the one set through
+    /// [`set_byte_order`](Self::set_byte_order) into the framebuffer, see
+    /// [`draw_raw`](Self::draw_raw). Every pixel is converted into the byte order of the
+    /// framebuffer on the way.
+    pub fn draw_raw_with_order(
+        &mut self,
+        area: &Rectangle,
+        data: &[u8],
+        order: ByteOrder,
+    ) -> Result<(), Error> {
+        let width = area.size.width as usize;
+        if data.len() != width * area.size.height as usize * 2 {
+            return Err(Error::InvalidData);
+        }
+        let drawable_area = self.drawable_area(area);
+        if drawable_area.is_zero_sized() || !self.has_buffer() {
+            return Ok(());
+        }
+        self.mark_rows_dirty(drawable_area.top_left.y as u32, drawable_area.size.height);
+
+        let display_width = self.get_dimensions().0 as usize;
+        let buffer_order = self.byte_order;
+        let skip_x = (drawable_area.top_left.x - area.top_left.x) as usize;
+        let skip_y = (drawable_area.top_left.y - area.top_left.y) as usize;
+        let rows = data
+            .chunks_exact(width * 2)
+            .skip(skip_y)
+            .take(drawable_area.size.height as usize);
+        for (y, row) in (drawable_area.top_left.y as usize..).zip(rows) {
+            let row = &row[skip_x * 2..(skip_x + drawable_area.size.width as usize) * 2];
+            let start = (y * display_width + drawable_area.top_left.x as usize) * BYTES_PER_PIXEL;
+            let pixels = self.buffer[start..].chunks_exact_mut(BYTES_PER_PIXEL);
+            for (pixel, color) in pixels.zip(row.chunks_exact(2)) {
+                let color = order.read([color[0], color[1]]);
+                pixel.copy_from_slice(&encode_pixel_with(color, buffer_order));
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "embedded-graphics")]
+    /// Draw an embedded-graphics `Framebuffer` with its top left corner at `top_left`, e.g. a
+    /// scene rendered offscreen with the full set of embedded-graphics primitives. Rows of 16 bit
+    /// framebuffers are packed without padding, so the data goes through the same bulk path as
+    /// [`draw_raw`](Self::draw_raw):
+    ///
+    /// ```ignore
+    /// let mut fb = Framebuffer::<Rgb565, RawU16, LittleEndian, 32, 32, 2048>::new();
+    /// Circle::new(Point::zero(), 32).into_styled(style).draw(&mut fb)?;
+    /// display.draw_framebuffer(&fb, Point::new(16, 16))?;
+    /// ```
+    ///
+    /// The byte order follows the `BO` parameter of the framebuffer instead of the one set
+    /// through [`set_byte_order`](Self::set_byte_order).
+    pub fn draw_framebuffer<BO, const W: usize, const H: usize, const N: usize>(
+        &mut self,
+        framebuffer: &Framebuffer<Rgb565, RawU16, BO, W, H, N>,
+        top_left: Point,
+    ) -> Result<(), Error>
+    where
+        BO: RawByteOrder,
+    {
+        let size = Size::new(W as u32, H as u32);
+        self.draw_raw_with_order(
+            &Rectangle::new(top_left, size),
+            framebuffer.data(),
+            BO::ORDER,
+        )
+    }
+
+    #[cfg(not(feature = "buffered"))]
+    /// Draw run-length encoded RGB565 data covering `area`, row by row. Frames with large solid
+    /// areas shrink a lot this way, e.g. when they come in over a slow link. The data is a
+    /// sequence of 3 byte runs:
+    ///
+    /// ```text
+    /// [count, color_high, color_low]
+    /// ```
+    ///
+    /// where `count` from 1 to 255 is the number of consecutive pixels of that colour, and the
+    /// colour is big endian RGB565 unless changed through [`set_byte_order`](Self::set_byte_order).
+    /// Runs continue across row boundaries and have to add up to exactly one pixel for every
+    /// point of `area`. Parts of the area outside of the display or the clip rectangle are dropped.
+    ///
+    /// Returns `Error::InvalidData` without drawing anything if the data is not made
+    /// of whole runs, holds a run of zero pixels or does not cover `area` exactly.
+    pub fn draw_rle(&mut self, area: &Rectangle, rle: &[u8]) -> Result<(), Error> {
+        if rle_len(rle) != Some(area.size.width as usize * area.size.height as usize) {
+            return Err(Error::InvalidData);
+        }
+        self.blit_colors(area, rle_colors(rle, self.byte_order))
+    }
+
+    #[cfg(not(feature = "buffered"))]
+    /// Send one colour for every point of `area`, row by row, through a single window. Points
+    /// outside of the display or the clip rectangle are skipped.
+    fn blit_colors<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Error>
+    where
+        I: Iterator<Item = u16>,
+    {
+        let drawable_area = self.drawable_area(area);
+        if drawable_area.is_zero_sized() {
+            return Ok(());
+        }
+
+        self.set_draw_area_rect(&drawable_area)?;
+
+        if drawable_area == *area {
+            return self.draw_colors(colors);
+        }
+        let visible = area
+            .points()
+            .zip(colors)
+            .filter(|(pos, _)| drawable_area.contains(*pos))
+            .map(|(_, color)| color);
+        self.draw_colors(visible)
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Draw run-length encoded RGB565 data covering `area` into the framebuffer, row by row. The
+    /// data is a sequence of 3 byte runs:
+    ///
+    /// ```text
+    /// [count, color_high, color_low]
+    /// ```
+    ///
+    /// where `count` from 1 to 255 is the number of consecutive pixels of that colour, and the
+    /// colour is big endian RGB565 unless changed through [`set_byte_order`](Self::set_byte_order).
+    /// Runs continue across row boundaries and have to add up to exactly one pixel for every
+    /// point of `area`. Parts of the area outside of the display or the clip rectangle are dropped.
+    ///
+    /// Returns `Error::InvalidData` without drawing anything if the data is not made
+    /// of whole runs, holds a run of zero pixels or does not cover `area` exactly.
+    pub fn draw_rle(&mut self, area: &Rectangle, rle: &[u8]) -> Result<(), Error> {
+        if rle_len(rle) != Some(area.size.width as usize * area.size.height as usize) {
+            return Err(Error::InvalidData);
+        }
+        self.blit_colors(area, rle_colors(rle, self.byte_order))
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Write one colour for every point of `area` into the framebuffer, row by row. Points
+    /// outside of the display or the clip rectangle are skipped.
+    fn blit_colors<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Error>
+    where
+        I: Iterator<Item = u16>,
+    {
+        let drawable_area = self.drawable_area(area);
+        if drawable_area.is_zero_sized() || !self.has_buffer() {
+            return Ok(());
+        }
+        self.mark_rows_dirty(drawable_area.top_left.y as u32, drawable_area.size.height);
+
+        let display_width = self.get_dimensions().0 as usize;
+        let order = self.byte_order;
+        area.points()
+            .zip(colors)
+            .filter(|(pos, _)| drawable_area.contains(*pos))
+            .for_each(|(pos, color)| {
+                let i = (pos.y as usize * display_width + pos.x as usize) * BYTES_PER_PIXEL;
+                self.buffer[i..i + BYTES_PER_PIXEL]
+                    .copy_from_slice(&encode_pixel_with(color, order));
+            });
+        Ok(())
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Invert the colours of `area` in the framebuffer, e.g. to highlight a selected menu entry.
+    /// Unlike [`flush_inverted`](Self::flush_inverted) this changes what the framebuffer holds,
+    /// inverting the same area again restores it. The area is clipped to the display and the clip
+    /// rectangle, and its rows are marked for the next
+    /// [`flush_dirty_rows`](Self::flush_dirty_rows).
+    pub fn invert_region(&mut self, area: &Rectangle) {
+        let area = self.drawable_area(area);
+        if area.is_zero_sized() || !self.has_buffer() {
+            return;
+        }
+        self.mark_rows_dirty(area.top_left.y as u32, area.size.height);
+
+        let display_width = self.get_dimensions().0 as usize;
+        let order = self.byte_order;
+        for pos in area.points() {
+            let i = (pos.y as usize * display_width + pos.x as usize) * BYTES_PER_PIXEL;
+            let pixel = &mut self.buffer[i..i + BYTES_PER_PIXEL];
+            let color = decode_pixel_with(pixel.try_into().unwrap(), order);
+            pixel.copy_from_slice(&encode_pixel_with(color ^ 0xFFFF, order));
+        }
+    }
+
+    /// Fill `area` with the colour that `f` returns for each pixel, given its `x` and `y` in the
+    /// current orientation, e.g. for a gradient or a plasma effect. The colours are streamed row
+    /// by row without an intermediate buffer, and `f` is only evaluated for pixels on the display
+    /// and within the clip rectangle.
+    pub fn fill_with<F>(&mut self, area: &Rectangle, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(u32, u32) -> Rgb565,
+    {
+        let area = self.drawable_area(area);
+        let colors = area
+            .points()
+            .map(|pos| RawU16::from(f(pos.x as u32, pos.y as u32)).into_inner());
+        self.blit_colors(&area, colors)
+    }
+
+    /// Draw raw RGB565 `src` data of `src_size` pixels, laid out like for
+    /// [`draw_raw`](Self::draw_raw), stretched or shrunk to cover `dst`, e.g. an icon at twice its
+    /// size. Every destination pixel takes the nearest source pixel, so integer factors repeat
+    /// pixels evenly and any other factor repeats or skips some of them. The result is streamed
+    /// row by row without scaling the source up front. Parts of `dst` outside of the display or
+    /// the clip rectangle are dropped.
+    ///
+    /// Returns `Error::InvalidData` if `src` does not hold exactly one pixel for every point of
+    /// `src_size`, or `src_size` is empty while `dst` is not.
+    pub fn blit_scaled(
+        &mut self,
+        src: &[u8],
+        src_size: Size,
+        dst: &Rectangle,
+    ) -> Result<(), Error> {
+        let (src_width, src_height) = (src_size.width as usize, src_size.height as usize);
+        if src.len() != src_width * src_height * 2 {
+            return Err(Error::InvalidData);
+        }
+        if dst.is_zero_sized() {
+            return Ok(());
+        }
+        if src_width == 0 || src_height == 0 {
+            return Err(Error::InvalidData);
+        }
+
+        let (dst_width, dst_height) = (dst.size.width as usize, dst.size.height as usize);
+        let order = self.byte_order;
+        let area = self.drawable_area(dst);
+        let colors = area.points().map(|pos| {
+            let dx = (pos.x - dst.top_left.x) as usize;
+            let dy = (pos.y - dst.top_left.y) as usize;
+            let i = ((dy * src_height / dst_height) * src_width + dx * src_width / dst_width) * 2;
+            order.read([src[i], src[i + 1]])
+        });
+        self.blit_colors(&area, colors)
+    }
+
+    /// Draw a progress bar covering `area`, with `fraction` of it (clamped to 0 to 1) in `fg` and
+    /// the rest in `bg`. A bar at least as wide as it is high fills from the left, a taller one
+    /// from the bottom up. Each part goes out through a single window, clipped to the display and
+    /// the clip rectangle.
+    pub fn draw_progress_bar(
+        &mut self,
+        area: &Rectangle,
+        fraction: f32,
+        fg: Rgb565,
+        bg: Rgb565,
+    ) -> Result<(), Error> {
+        let Size { width, height } = area.size;
+        let horizontal = width >= height;
+        let length = if horizontal { width } else { height };
+        let filled = ((length as f32 * fraction.clamp(0.0, 1.0) + 0.5) as u32).min(length);
+        let (done, rest) = if horizontal {
+            (
+                Rectangle::new(area.top_left, Size::new(filled, height)),
+                Rectangle::new(
+                    area.top_left + Point::new(filled as i32, 0),
+                    Size::new(width - filled, height),
+                ),
+            )
+        } else {
+            (
+                Rectangle::new(
+                    area.top_left + Point::new(0, (height - filled) as i32),
+                    Size::new(width, filled),
+                ),
+                Rectangle::new(area.top_left, Size::new(width, height - filled)),
+            )
+        };
+
+        for (part, color) in [(done, fg), (rest, bg)] {
+            let part = self.drawable_area(&part);
+            let pixels = part.size.width as usize * part.size.height as usize;
+            let color = RawU16::from(color).into_inner();
+            self.blit_colors(&part, core::iter::repeat_n(color, pixels))?;
+        }
+        Ok(())
+    }
+
+    /// Draw the outline of `area` with its corners rounded off by quarter circles of `radius`
+    /// pixels, e.g. the frame of a button. The straight edges and each row of the corners go out
+    /// as horizontal runs through one window each. A radius too large for the rectangle is reduced
+    /// until the corners meet, so a square with a large radius becomes a circle. The outline is
+    /// clipped to the display and the clip rectangle.
+    pub fn draw_rounded_rect(
+        &mut self,
+        area: &Rectangle,
+        radius: u16,
+        color: Rgb565,
+    ) -> Result<(), Error> {
+        let Some((x0, y0, x1, y1, r)) = rounded_rect_corners(area, radius) else {
+            return Ok(());
+        };
+        let color = RawU16::from(color).into_inner();
+        let (left, right, top, bottom) = (x0 + r, x1 - r, y0 + r, y1 - r);
+        for dy in 0..=r {
+            let half = circle_half_width(r, dy);
+            // Every row of a corner runs from where the row further out ended to its own extent
+            let inner = if dy == r {
+                0
+            } else {
+                (circle_half_width(r, dy + 1) + 1).min(half)
+            };
+            for y in [top - dy, bottom + dy] {
+                if inner == 0 {
+                    self.fill_span(left - half, right + half, y, color)?;
+                } else {
+                    self.fill_span(left - half, left - inner, y, color)?;
+                    self.fill_span(right + inner, right + half, y, color)?;
+                }
+                if top == bottom && dy == 0 {
+                    break;
+                }
+            }
+        }
+        for y in top + 1..bottom {
+            self.fill_span(x0, x0, y, color)?;
+            self.fill_span(x1, x1, y, color)?;
+        }
+        Ok(())
+    }
+
+    /// Fill `area` with its corners rounded off by quarter circles of `radius` pixels, e.g. the
+    /// background of a button. Every row goes out as a single run, the radius is reduced like for
+    /// [`draw_rounded_rect`](Self::draw_rounded_rect) and the shape is clipped to the display and
+    /// the clip rectangle.
+    pub fn fill_rounded_rect(
+        &mut self,
+        area: &Rectangle,
+        radius: u16,
+        color: Rgb565,
+    ) -> Result<(), Error> {
+        let Some((x0, y0, x1, y1, r)) = rounded_rect_corners(area, radius) else {
+            return Ok(());
+        };
+        let color = RawU16::from(color).into_inner();
+        let (left, right, top, bottom) = (x0 + r, x1 - r, y0 + r, y1 - r);
+        for dy in 0..=r {
+            let half = circle_half_width(r, dy);
+            self.fill_span(left - half, right + half, top - dy, color)?;
+            if top - dy != bottom + dy {
+                self.fill_span(left - half, right + half, bottom + dy, color)?;
+            }
+        }
+        for y in top + 1..bottom {
+            self.fill_span(x0, x1, y, color)?;
+        }
+        Ok(())
+    }
+
+    /// Fill `area` like [`fill_with`](Self::fill_with) from colours of 8 bits per channel, which
+    /// are dithered down to RGB565 if enabled through [`set_dither`](Self::set_dither)
+    pub fn fill_with_rgb888<F>(&mut self, area: &Rectangle, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(u32, u32) -> Rgb888,
+    {
+        let dither = self.dither;
+        let area = self.drawable_area(area);
+        let colors = area.points().map(|pos| {
+            let (x, y) = (pos.x as u32, pos.y as u32);
+            if dither {
+                dither_rgb888(f(x, y), x, y)
+            } else {
+                RawU16::from(Rgb565::from(f(x, y))).into_inner()
+            }
+        });
+        self.blit_colors(&area, colors)
+    }
+
+    /// Draw a bitmap of one bit per pixel, `width` pixels wide, with its top left corner at
+    /// `top_left`, e.g. an icon. Every row starts on a byte boundary with its leftmost pixel in
+    /// the most significant bit, the layout of an embedded-graphics `ImageRaw<BinaryColor>`. Set
+    /// bits are drawn in `fg` and clear ones in `bg`, or left as they are if `bg` is `None`. An
+    /// opaque bitmap goes out through a single window, a transparent one as runs of set bits.
+    /// Parts of the bitmap outside of the display or the clip rectangle are dropped.
+    ///
+    /// Returns `Error::InvalidData` if `width` is zero or `data` does not hold whole rows.
+    pub fn draw_mono_bitmap(
+        &mut self,
+        top_left: Point,
+        width: u8,
+        data: &[u8],
+        fg: Rgb565,
+        bg: Option<Rgb565>,
+    ) -> Result<(), Error> {
+        let stride = (width as usize).div_ceil(8);
+        if stride == 0 || !data.chunks_exact(stride).remainder().is_empty() {
+            return Err(Error::InvalidData);
+        }
+        let area = Rectangle::new(
+            top_left,
+            Size::new(width as u32, (data.len() / stride) as u32),
+        );
+        let bit = |pos: Point| {
+            let (x, y) = ((pos.x - top_left.x) as usize, (pos.y - top_left.y) as usize);
+            data[y * stride + x / 8] & (0x80 >> (x % 8)) != 0
+        };
+        let fg = RawU16::from(fg).into_inner();
+
+        if let Some(bg) = bg {
+            let bg = RawU16::from(bg).into_inner();
+            return self.blit_colors(
+                &area,
+                area.points().map(|pos| if bit(pos) { fg } else { bg }),
+            );
+        }
+
+        let mut pixels = [(0u8, 0u8, 0u16); 32];
+        let mut len = 0;
+        let visible = self.drawable_area(&area);
+        for pos in visible.points().filter(|&pos| bit(pos)) {
+            pixels[len] = (pos.x as u8, pos.y as u8, fg);
+            len += 1;
+            if len == pixels.len() {
+                self.set_pixels(&pixels)?;
+                len = 0;
+            }
+        }
+        self.set_pixels(&pixels[..len])
+    }
+}
+
+#[cfg(feature = "graphics")]
+/// Number of pixels in the run-length encoded data of `draw_rle`, `None` if it is malformed
+fn rle_len(rle: &[u8]) -> Option<usize> {
+    let mut runs = rle.chunks_exact(3);
+    if !runs.remainder().is_empty() {
+        return None;
+    }
+    runs.try_fold(0, |len, run| match run[0] {
+        0 => None,
+        count => Some(len + count as usize),
+    })
+}
+
+#[cfg(feature = "graphics")]
+/// Expand the runs of `draw_rle` into one colour per pixel
+fn rle_colors(rle: &[u8], order: ByteOrder) -> impl Iterator<Item = u16> + '_ {
+    rle.chunks_exact(3)
+        .flat_map(move |run| core::iter::repeat_n(order.read([run[1], run[2]]), run[0] as usize))
+}
+
+#[cfg(feature = "graphics")]
+/// Reduce `color` to RGB565 with a 4x4 Bayer matrix, adding a threshold that depends on the
+/// position before the low bits of each channel are dropped
+fn dither_rgb888(color: Rgb888, x: u32, y: u32) -> u16 {
+    const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+    let threshold = BAYER[y as usize % 4][x as usize % 4] as u16;
+    // Spread the threshold over the bits that get dropped, 3 for red and blue and 2 for green
+    let channel =
+        |value: u8, shift: u16| (value as u16 + (threshold << shift >> 4)).min(255) >> shift;
+    channel(color.r(), 3) << 11 | channel(color.g(), 2) << 5 | channel(color.b(), 3)
+}
+
+#[cfg(feature = "graphics")]
+/// Get the inclusive corners of a rounded rectangle and its radius, reduced so that the corner
+/// centres do not cross, `None` if the rectangle is empty
+fn rounded_rect_corners(area: &Rectangle, radius: u16) -> Option<(i32, i32, i32, i32, i32)> {
+    if area.is_zero_sized() {
+        return None;
+    }
+    let (width, height) = (area.size.width as i32, area.size.height as i32);
+    let r = (radius as i32).min((width.min(height) - 1) / 2);
+    let (x0, y0) = (area.top_left.x, area.top_left.y);
+    Some((x0, y0, x0 + width - 1, y0 + height - 1, r))
+}
+
+#[cfg(feature = "graphics")]
+/// Half the width of the row `dy` rows away from the centre of a circle of radius `r`, with the
+/// same pixels as the midpoint algorithm of `draw_circle`: inside if x² + y² <= r² + r
+fn circle_half_width(r: i32, dy: i32) -> i32 {
+    let limit = r * r + r - dy * dy;
+    let mut half = r;
+    while half > 0 && half * half > limit {
+        half -= 1;
+    }
+    half
+}
+
+#[cfg(all(feature = "graphics", feature = "buffered"))]
+impl<DI: WriteOnlyDataCommand> GraphicsMode<'_, DI> {
+    /// Copy `src_area` from the framebuffer of another display into this framebuffer, with the
+    /// top left corner of the area placed at `dst_top_left`. Source pixels matching `color_key`
+    /// are treated as transparent and leave the destination untouched. Parts of the area that fall
+    /// outside of either framebuffer or the clip rectangle are dropped. Only the framebuffer is
+    /// changed, call `flush` to show the result.
+    pub fn composite_from<SDI: WriteOnlyDataCommand>(
+        &mut self,
+        src: &GraphicsMode<'_, SDI>,
+        src_area: Rectangle,
+        dst_top_left: Point,
+        color_key: Option<Rgb565>,
+    ) {
+        if !self.has_buffer() || !src.has_buffer() {
+            return;
+        }
+        let offset = dst_top_left - src_area.top_left;
+        let src_area = src_area.intersection(&src.bounding_box());
+        let dst_area =
+            self.drawable_area(&Rectangle::new(src_area.top_left + offset, src_area.size));
+
+        let key = color_key
+            .map(|color| encode_pixel_with(RawU16::from(color).into_inner(), src.byte_order));
+        let src_width = src.get_dimensions().0 as usize;
+        let dst_width = self.get_dimensions().0 as usize;
+        if !dst_area.is_zero_sized() {
+            self.mark_rows_dirty(dst_area.top_left.y as u32, dst_area.size.height);
+        }
+
+        for pos in dst_area.points() {
+            let src_pos = pos - offset;
+            let si = (src_pos.y as usize * src_width + src_pos.x as usize) * BYTES_PER_PIXEL;
+            let pixel =
+                <[u8; BYTES_PER_PIXEL]>::try_from(&src.buffer[si..si + BYTES_PER_PIXEL]).unwrap();
+            if key == Some(pixel) {
+                continue;
+            }
+            let pixel = if src.byte_order == self.byte_order {
+                pixel
+            } else {
+                encode_pixel_with(decode_pixel_with(pixel, src.byte_order), self.byte_order)
+            };
+            let di = (pos.y as usize * dst_width + pos.x as usize) * BYTES_PER_PIXEL;
+            self.buffer[di..di + BYTES_PER_PIXEL].copy_from_slice(&pixel);
+        }
+    }
+}
+
+#[cfg(feature = "buffered")]
+/// Bits of `rows` rows starting at `y` in the dirty row mask, rows past 128 are dropped
+fn row_mask(y: u32, rows: u32) -> u128 {
+    let rows = rows.min(128u32.saturating_sub(y));
+    match rows {
+        0 => 0,
+        128 => u128::MAX,
+        rows => ((1 << rows) - 1) << y,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+    use crate::properties::DisplaySize;
+
+    #[cfg(not(feature = "buffered"))]
+    #[test]
+    fn set_pixels_sends_one_window_per_run() {
+        use crate::mock::Transfer;
+
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let pixels: Vec<_> = (10..20).map(|x| (x, 5, 0xF800 | x as u16)).collect();
+
+        display.set_pixels(&pixels).unwrap();
+        let batched = mock.take();
+        let data: Vec<_> = pixels
+            .iter()
+            .flat_map(|&(_, _, color)| color.to_be_bytes())
+            .collect();
+        assert_eq!(
+            batched,
+            [
+                Transfer::Command(vec![0x15]),
+                Transfer::Data(vec![10, 19]),
+                Transfer::Command(vec![0x75]),
+                Transfer::Data(vec![5, 5]),
+                Transfer::Command(vec![0x5C]),
+                Transfer::Data(data),
+            ]
+        );
+
+        for &(x, y, color) in &pixels {
+            display.set_pixel(x as u32, y as u32, color).unwrap();
+        }
+        let single = mock.take();
+        assert_eq!(single.len(), pixels.len() * 6);
+        assert_eq!(mock::ram_data(&single), mock::ram_data(&batched));
+    }
+
+    #[cfg(not(feature = "buffered"))]
+    #[test]
+    fn set_pixels_rotates_runs() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x96, DisplayRotation::Rotate90);
+        let pixels: Vec<_> = (10..20).map(|x| (x, 5, 0x07E0)).collect();
+
+        display.set_pixels(&pixels).unwrap();
+        let transfers = mock.take();
+        assert_eq!(mock::args(&transfers, 0x15), [[5, 5]]);
+        assert_eq!(mock::args(&transfers, 0x75), [[10, 19]]);
+        assert_eq!(mock::ram_writes(&transfers).len(), 1);
+    }
+
+    #[cfg(feature = "buffered")]
+    #[test]
+    fn set_pixels_only_touches_the_framebuffer() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let pixels: Vec<_> = (10..20).map(|x| (x, 5, 0xF800)).collect();
+
+        display.set_pixels(&pixels).unwrap();
+        assert_eq!(mock.take(), []);
+        let row = &display.fb()[5 * 128 * BYTES_PER_PIXEL..6 * 128 * BYTES_PER_PIXEL];
+        let lit = row.chunks_exact(BYTES_PER_PIXEL).enumerate();
+        for (x, pixel) in lit {
+            let expected = if (10..20).contains(&x) { 0xF800 } else { 0 };
+            assert_eq!(
+                decode_pixel_with(pixel.try_into().unwrap(), ByteOrder::BigEndian),
+                expected
+            );
+        }
+    }
+
+    #[cfg(feature = "buffered")]
+    #[test]
+    fn new_with_framebuffer_borrows_a_stack_framebuffer() {
+        let mut framebuffer = FrameBuffer::<128, 96>::new();
+        let (display, mock) = mock::display(DisplaySize::Display128x96, DisplayRotation::Rotate0);
+        let mut display = GraphicsMode::new_with_framebuffer(display, &mut framebuffer)
+            .unwrap_or_else(|_| panic!("framebuffer matches the display"));
+
+        display.set_pixel(10, 5, 0xF800);
+        display.flush().unwrap();
+        let data = mock::ram_data(&mock.take());
+        assert_eq!(data.len(), 128 * 96 * 2);
+        assert_eq!(data[(5 * 128 + 10) * 2..][..2], [0xF8, 0x00]);
+        assert!(data.iter().filter(|&&byte| byte != 0).count() == 1);
+
+        drop(display);
+        let pixel = &framebuffer.as_slice()[(5 * 128 + 10) * BYTES_PER_PIXEL..][..BYTES_PER_PIXEL];
+        assert_eq!(
+            decode_pixel_with(pixel.try_into().unwrap(), ByteOrder::BigEndian),
+            0xF800
+        );
+    }
+
+    #[cfg(feature = "buffered")]
+    #[test]
+    fn new_with_framebuffer_hands_back_a_framebuffer_of_the_other_size() {
+        let mut framebuffer = FrameBuffer::<128, 128>::new();
+        let (display, mock) = mock::display(DisplaySize::Display128x96, DisplayRotation::Rotate0);
+
+        let (display, _) = GraphicsMode::new_with_framebuffer(display, &mut framebuffer)
+            .err()
+            .expect("128x128 framebuffer for a 128x96 display");
+        assert_eq!(display.get_size().dimensions(), (128, 96));
+        assert_eq!(mock.take(), []);
+    }
+
+    #[cfg(all(feature = "graphics", not(feature = "buffered")))]
+    #[test]
+    fn draw_target_returns_interface_errors() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let area = Rectangle::new(Point::new(4, 4), Size::new(8, 8));
+
+        mock.set_fail(true);
+        let result = display.fill_solid(&area, Rgb565::RED);
+        assert!(matches!(
+            result,
+            Err(Error::Interface(DisplayError::BusWriteError))
+        ));
+        let result = display.set_pixel(1, 1, 0xF800);
+        assert!(matches!(
+            result,
+            Err(Error::Interface(DisplayError::BusWriteError))
+        ));
+
+        mock.set_fail(false);
+        display.fill_solid(&area, Rgb565::RED).unwrap();
+        assert_eq!(mock::ram_data(&mock.take()).len(), 8 * 8 * 2);
+    }
+
+    #[cfg(all(feature = "graphics", feature = "buffered"))]
+    #[test]
+    fn draw_target_returns_interface_errors() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let area = Rectangle::new(Point::new(4, 4), Size::new(8, 8));
+
+        mock.set_fail(true);
+        display.fill_solid(&area, Rgb565::RED).unwrap();
+        assert!(matches!(
+            display.flush(),
+            Err(Error::Interface(DisplayError::BusWriteError))
+        ));
+        display.set_auto_flush(true);
+        let result = display.fill_solid(&area, Rgb565::RED);
+        assert!(matches!(
+            result,
+            Err(Error::Interface(DisplayError::BusWriteError))
+        ));
+
+        mock.set_fail(false);
+        display.flush().unwrap();
+        assert_eq!(mock::ram_data(&mock.take()).len(), 128 * 128 * 2);
+    }
+
+    #[cfg(all(feature = "graphics", feature = "buffered"))]
+    #[test]
+    fn composite_from_skips_the_color_key_and_clips() {
+        let (mut dst, mock) = mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let (mut src, _) = mock::graphics(DisplaySize::Display128x96, DisplayRotation::Rotate0);
+        src.clear_to(0x07E0).unwrap();
+        src.set_pixel(1, 1, 0xF800);
+        dst.clear_to(0x001F).unwrap();
+
+        let area = Rectangle::new(Point::new(0, 0), Size::new(4, 4));
+        dst.composite_from(&src, area, Point::new(10, 20), Some(Rgb565::GREEN));
+        assert_eq!(mock::pixel(&dst, 11, 21), 0xF800);
+        assert_eq!(mock::pixel(&dst, 10, 20), 0x001F);
+        assert_eq!(mock::pixel(&dst, 13, 23), 0x001F);
+
+        dst.composite_from(&src, area, Point::new(126, 126), None);
+        assert_eq!(mock::pixel(&dst, 126, 126), 0x07E0);
+        assert_eq!(mock::pixel(&dst, 127, 127), 0xF800);
+        assert_eq!(mock::pixel(&dst, 125, 125), 0x001F);
+        assert_eq!(mock.take(), []);
+    }
+
+    #[test]
+    fn flush_rows_checks_the_edges() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let row = [0xAB; 128 * 2];
+
+        let result = display.flush_rows(0, 1, &row[..row.len() - 1]);
+        assert!(matches!(result, Err(Error::InvalidData)));
+        display.flush_rows(0, 0, &[]).unwrap();
+        display.flush_rows(128, 0, &[]).unwrap();
+        assert_eq!(mock.take(), []);
+
+        display.flush_rows(127, 1, &row).unwrap();
+        let transfers = mock.take();
+        assert_eq!(mock::args(&transfers, 0x75), [[127, 127]]);
+        assert_eq!(mock::ram_data(&transfers), row);
+        let rows = [0xAB; 2 * 128 * 2];
+        assert!(matches!(
+            display.flush_rows(127, 2, &rows),
+            Err(Error::OutOfBounds)
+        ));
+        assert_eq!(mock.take(), []);
+    }
+
+    #[cfg(all(feature = "graphics", feature = "buffered"))]
+    #[test]
+    fn fill_contiguous_indexes_the_rotated_framebuffer() {
+        let (mut display, _) =
+            mock::graphics(DisplaySize::Display128x96, DisplayRotation::Rotate90);
+        let (mut reference, _) =
+            mock::graphics(DisplaySize::Display128x96, DisplayRotation::Rotate90);
+        assert_eq!(display.get_dimensions(), (96, 128));
+
+        // Wider than tall, and only fits in the rotated orientation
+        let area = Rectangle::new(Point::new(88, 120), Size::new(8, 4));
+        let colors = (0..32).map(|i| Rgb565::new(i, 2 * i, 31 - i));
+        display.fill_contiguous(&area, colors.clone()).unwrap();
+        for (pos, color) in area.points().zip(colors) {
+            let color = RawU16::from(color).into_inner();
+            reference.set_pixel(pos.x as u32, pos.y as u32, color);
+        }
+
+        assert_eq!(display.fb(), reference.fb());
+        assert_eq!(mock::pixel(&display, 88, 120), reference_color(0));
+        assert_eq!(mock::pixel(&display, 95, 123), reference_color(31));
+        assert_eq!(mock::pixel(&display, 87, 120), 0);
+    }
+
+    #[cfg(all(feature = "graphics", feature = "buffered"))]
+    #[test]
+    fn fill_contiguous_indexes_the_framebuffer_upside_down() {
+        let rotations = [
+            (DisplayRotation::Rotate180, (128, 96), Point::new(120, 92)),
+            (DisplayRotation::Rotate270, (96, 128), Point::new(88, 124)),
+        ];
+        for (rotation, dimensions, top_left) in rotations {
+            let (mut display, _) = mock::graphics(DisplaySize::Display128x96, rotation);
+            let (mut reference, _) = mock::graphics(DisplaySize::Display128x96, rotation);
+            assert_eq!(display.get_dimensions(), dimensions);
+
+            // In the bottom right corner, the last pixel of the framebuffer
+            let area = Rectangle::new(top_left, Size::new(8, 4));
+            let colors = (0..32).map(|i| Rgb565::new(i, 2 * i, 31 - i));
+            display.fill_contiguous(&area, colors.clone()).unwrap();
+            for (pos, color) in area.points().zip(colors) {
+                let color = RawU16::from(color).into_inner();
+                reference.set_pixel(pos.x as u32, pos.y as u32, color);
+            }
+
+            let (x, y) = (top_left.x as usize, top_left.y as usize);
+            assert_eq!(display.fb(), reference.fb());
+            assert_eq!(mock::pixel(&display, x, y), reference_color(0));
+            assert_eq!(mock::pixel(&display, x + 7, y + 3), reference_color(31));
+            assert_eq!(mock::pixel(&display, x - 1, y), 0);
+        }
+    }
+
+    #[cfg(all(feature = "graphics", feature = "buffered"))]
+    /// Framebuffer value of the `i`th colour of the `fill_contiguous` tests
+    fn reference_color(i: u8) -> u16 {
+        let color = RawU16::from(Rgb565::new(i, 2 * i, 31 - i)).into_inner();
+        decode_pixel_with(
+            encode_pixel_with(color, ByteOrder::BigEndian),
+            ByteOrder::BigEndian,
+        )
+    }
+
+    #[cfg(feature = "buffered")]
+    #[test]
+    fn flip_horizontal_mirrors_rows_on_the_way_out() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        display.set_pixel(0, 0, 0xF800);
+        display.set_pixel(3, 1, 0x07E0);
+        display.set_flip_horizontal(true);
+
+        display.flush().unwrap();
+        let data = mock::ram_data(&mock.take());
+        let at = |x: usize, y: usize| {
+            u16::from_be_bytes([data[(y * 128 + x) * 2], data[(y * 128 + x) * 2 + 1]])
+        };
+        assert_eq!(at(127, 0), 0xF800);
+        assert_eq!(at(124, 1), 0x07E0);
+        assert_eq!(at(0, 0), 0);
+        assert_eq!(mock::pixel(&display, 0, 0), 0xF800);
+
+        let area = Region {
+            x: 0,
+            y: 0,
+            width: 4,
+            height: 2,
+        };
+        display.flush_area(area).unwrap();
+        let transfers = mock.take();
+        assert_eq!(mock::args(&transfers, 0x15), [[124, 127]]);
+        let data = mock::ram_data(&transfers);
+        assert_eq!(data[6..8], [0xF8, 0x00]);
+        assert_eq!(data[8..10], [0x07, 0xE0]);
+    }
+
+    #[cfg(all(feature = "graphics", feature = "buffered"))]
+    #[test]
+    fn detached_buffer_makes_drawing_a_no_op() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let buffer = display.take_buffer().unwrap();
+        assert!(!display.has_buffer());
+        assert!(display.take_buffer().is_none());
+
+        display.set_pixel(1, 1, 0xF800);
+        let area = Rectangle::new(Point::new(0, 0), Size::new(4, 4));
+        display.fill_solid(&area, Rgb565::RED).unwrap();
+        assert!(matches!(display.flush(), Err(Error::BufferSize)));
+        assert!(matches!(display.flush_continuous(), Err(Error::BufferSize)));
+        let bounds = display.display_bounds();
+        assert!(matches!(display.flush_area(bounds), Err(Error::BufferSize)));
+        display.flush_dirty_rows().unwrap();
+        assert_eq!(mock.take(), []);
+
+        assert!(display.restore_buffer(vec![0; 16].leak()).is_err());
+        display.restore_buffer(buffer).unwrap();
+        assert_eq!(mock::pixel(&display, 1, 1), 0);
+        display.flush_dirty_rows().unwrap();
+        assert_eq!(mock::ram_data(&mock.take()).len(), 128 * 128 * 2);
+
+        display.take_buffer().unwrap();
+        assert!(matches!(
+            display.flush_and_release(),
+            Err(Error::BufferSize)
+        ));
+        assert_eq!(mock.take(), []);
+    }
+
+    #[cfg(feature = "buffered")]
+    #[test]
+    fn set_pixel_ignores_pixels_off_the_display() {
+        let (mut display, _) = mock::graphics(DisplaySize::Display128x96, DisplayRotation::Rotate0);
+        display.set_pixel(128, 0, 0xF800);
+        display.set_pixel(0, 96, 0xF800);
+        display.set_pixel(u32::MAX, u32::MAX, 0xF800);
+        // Would land in the next row without checking x
+        display.set_pixel(200, 0, 0xF800);
+        assert!(display.fb().iter().all(|&byte| byte == 0));
+    }
+
+    #[cfg(not(feature = "buffered"))]
+    #[test]
+    fn set_pixel_ignores_pixels_off_the_display() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x96, DisplayRotation::Rotate0);
+        display.set_pixel(128, 0, 0xF800).unwrap();
+        display.set_pixel(0, 96, 0xF800).unwrap();
+        display.set_pixel(u32::MAX, u32::MAX, 0xF800).unwrap();
+        // Would be truncated to column 44 without checking x
+        display.set_pixel(300, 0, 0xF800).unwrap();
+        assert_eq!(mock.take(), []);
+    }
+
+    #[cfg(feature = "buffered")]
+    #[test]
+    fn flush_inverted_leaves_the_framebuffer_alone() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        display.set_pixel(0, 0, 0xF800);
+        let before = display.fb().to_vec();
+
+        display.flush_inverted().unwrap();
+        let data = mock::ram_data(&mock.take());
+        assert_eq!(data.len(), 128 * 128 * 2);
+        assert_eq!(data[..4], [0x07, 0xFF, 0xFF, 0xFF]);
+        assert!(data[2..].iter().all(|&byte| byte == 0xFF));
+        assert_eq!(display.fb(), before);
+
+        display.flush().unwrap();
+        let data = mock::ram_data(&mock.take());
+        assert_eq!(data[..4], [0xF8, 0x00, 0x00, 0x00]);
+    }
+
+    #[cfg(feature = "buffered")]
+    #[test]
+    fn flush_pixel_sends_two_bytes() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x96, DisplayRotation::Rotate90);
+        display.set_pixel(10, 100, 0xF800);
+
+        display.flush_pixel(10, 100).unwrap();
+        let transfers = mock.take();
+        assert_eq!(mock::args(&transfers, 0x15), [[100, 100]]);
+        assert_eq!(mock::args(&transfers, 0x75), [[10, 10]]);
+        assert_eq!(mock::ram_writes(&transfers), [[0xF8, 0x00]]);
+
+        assert!(matches!(
+            display.flush_pixel(96, 0),
+            Err(Error::OutOfBounds)
+        ));
+        assert_eq!(mock.take(), []);
+    }
+
+    #[cfg(feature = "buffered")]
+    #[test]
+    fn flush_area_programs_one_window() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+
+        let area = Region {
+            x: 120,
+            y: 10,
+            width: 20,
+            height: 3,
+        };
+        display.flush_area(area).unwrap();
+        let transfers = mock.take();
+        assert_eq!(mock::commands(&transfers), [0x15, 0x75, 0x5C]);
+        assert_eq!(mock::args(&transfers, 0x15), [[120, 127]]);
+        assert_eq!(mock::args(&transfers, 0x75), [[10, 12]]);
+        assert_eq!(mock::ram_writes(&transfers), [[0; 16]; 3]);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn byte_order_round_trips_to_big_endian_on_the_bus() {
+        for order in [ByteOrder::BigEndian, ByteOrder::LittleEndian] {
+            let (mut display, mock) =
+                mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+            display.set_byte_order(order);
+            assert_eq!(display.byte_order(), order);
+
+            let data = [order.write(0xF800), order.write(0x001F)].concat();
+            let area = Rectangle::new(Point::new(0, 0), Size::new(2, 1));
+            display.draw_raw(&area, &data).unwrap();
+            #[cfg(all(feature = "buffered", not(feature = "palette8")))]
+            assert_eq!(display.fb()[..4], data);
+            #[cfg(feature = "buffered")]
+            display.flush().unwrap();
+
+            let sent = mock::ram_data(&mock.take());
+            assert_eq!(sent[..4], [0xF8, 0x00, 0x00, 0x1F]);
+        }
+    }
+
+    #[test]
+    fn draw_splash_fills_the_active_rows() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        display.set_active_rows(64).unwrap();
+        mock.take();
+
+        assert!(matches!(
+            display.draw_splash(&[0; 128 * 128 * 2]),
+            Err(Error::InvalidData)
+        ));
+        display.draw_splash(&[0; 128 * 64 * 2]).unwrap();
+        let transfers = mock.take();
+        assert_eq!(mock::args(&transfers, 0x75), [[0, 63]]);
+        assert_eq!(mock::ram_data(&transfers).len(), 128 * 64 * 2);
+    }
+
+    #[cfg(all(feature = "graphics", not(feature = "buffered")))]
+    #[test]
+    fn draw_iter_batches_text_into_runs() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        // Column by column, the way the bundled font is laid out
+        let mut points = Vec::new();
+        let glyphs = "Hi".chars().filter_map(font::glyph);
+        for (i, columns) in glyphs.enumerate() {
+            for (column, bits) in columns.iter().enumerate() {
+                let x = i * CHAR_WIDTH as usize + column;
+                points.extend((0..8).filter(|row| bits >> row & 1 == 1).map(|y| (x, y)));
+            }
+        }
+        // A run starts at every pixel without a lit neighbour on its left
+        let runs = points
+            .iter()
+            .filter(|&&(x, y)| x == 0 || !points.contains(&(x - 1, y)))
+            .count();
+
+        let pixels = points
+            .iter()
+            .map(|&(x, y)| Pixel(Point::new(x as i32, y), Rgb565::WHITE));
+        display.draw_iter(pixels).unwrap();
+        let batched = mock.take();
+        assert_eq!(batched.len(), runs * 6);
+        assert_eq!(mock::ram_data(&batched).len(), points.len() * 2);
+
+        for &(x, y) in &points {
+            display.set_pixel(x as u32, y as u32, 0xFFFF).unwrap();
+        }
+        let naive = mock.take();
+        assert_eq!(naive.len(), points.len() * 6);
+        assert!(batched.len() < naive.len());
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn fill_contiguous_offscreen_sends_nothing() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let left = Rectangle::new(Point::new(-20, 10), Size::new(10, 10));
+        let below = Rectangle::new(Point::new(10, 128), Size::new(10, 10));
+
+        for area in [left, below] {
+            let colors = core::iter::repeat(Rgb565::RED);
+            display.fill_contiguous(&area, colors).unwrap();
+        }
+        assert_eq!(mock.take(), []);
+        #[cfg(feature = "buffered")]
+        assert!(display.fb().iter().all(|&byte| byte == 0));
+    }
+
+    #[cfg(feature = "buffered")]
+    #[test]
+    fn flush_dirty_rows_sends_only_changed_rows() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        display.flush().unwrap();
+        mock.take();
+
+        display.set_pixel(0, 3, 0xF800);
+        display.set_pixel(5, 4, 0xF800);
+        display.set_pixel(9, 10, 0xF800);
+        display.flush_dirty_rows().unwrap();
+        let transfers = mock.take();
+        assert_eq!(mock::args(&transfers, 0x75), [[3, 4], [10, 10]]);
+        assert_eq!(mock::commands(&transfers).len(), 6);
+        assert_eq!(mock::ram_data(&transfers).len(), 3 * 128 * 2);
+
+        display.flush_dirty_rows().unwrap();
+        assert_eq!(mock.take(), []);
+
+        // A full frame for comparison
+        display.set_pixel(0, 3, 0x001F);
+        display.flush().unwrap();
+        assert_eq!(mock::ram_data(&mock.take()).len(), 128 * 128 * 2);
+    }
+
+    #[cfg(not(feature = "buffered"))]
+    #[test]
+    fn pixel_run_programs_the_window_once() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+
+        display.begin_pixels();
+        for x in 20..30 {
+            display.set_pixel(x, 7, 0xF800).unwrap();
+        }
+        display.end_pixels();
+        let transfers = mock.take();
+        assert_eq!(mock::commands(&transfers), [0x15, 0x75, 0x5C]);
+        assert_eq!(transfers.len(), 6 + 9);
+        assert_eq!(mock::ram_data(&transfers), [0xF8, 0x00].repeat(10));
+
+        // Off the run, the window is programmed again
+        display.begin_pixels();
+        display.set_pixel(40, 7, 0xF800).unwrap();
+        display.end_pixels();
+        display.set_pixel(41, 7, 0xF800).unwrap();
+        assert_eq!(mock::commands(&mock.take()).len(), 6);
+    }
+
+    #[cfg(all(feature = "graphics", feature = "buffered"))]
+    #[test]
+    fn restore_undoes_drawing_since_the_snapshot() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        display.set_pixel(3, 4, 0x07E0);
+        let before = display.fb().to_vec();
+        let mut snapshot = vec![0; before.len()];
+        display.snapshot(&mut snapshot).unwrap();
+        assert_eq!(snapshot, before);
+
+        let area = Rectangle::new(Point::new(0, 0), Size::new(16, 16));
+        display.fill_solid(&area, Rgb565::RED).unwrap();
+        assert_ne!(display.fb(), before);
+        display.flush_dirty_rows().unwrap();
+        mock.take();
+
+        display.restore(&snapshot).unwrap();
+        assert_eq!(display.fb(), before);
+        // Every row goes out again, not only the ones drawn over
+        display.flush_dirty_rows().unwrap();
+        assert_eq!(mock::ram_data(&mock.take()).len(), 128 * 128 * 2);
+    }
+
+    #[cfg(feature = "buffered")]
+    #[test]
+    fn snapshot_and_restore_reject_a_wrong_length() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        display.set_pixel(3, 4, 0x07E0);
+        display.flush_dirty_rows().unwrap();
+        mock.take();
+        let before = display.fb().to_vec();
+        for len in [0, before.len() - 1, before.len() + 1] {
+            let mut snapshot = vec![0xAA; len];
+            assert!(matches!(
+                display.snapshot(&mut snapshot),
+                Err(Error::BufferSize)
+            ));
+            assert!(snapshot.iter().all(|&byte| byte == 0xAA));
+            assert!(matches!(display.restore(&snapshot), Err(Error::BufferSize)));
+            assert_eq!(display.fb(), before);
+        }
+        // A rejected restore marks nothing for the next flush
+        display.flush_dirty_rows().unwrap();
+        assert_eq!(mock.take(), []);
+    }
+
+    #[cfg(all(feature = "graphics", feature = "buffered"))]
+    #[test]
+    fn auto_flush_sends_one_window_per_draw() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let area = Rectangle::new(Point::new(4, 4), Size::new(8, 8));
+        display.fill_solid(&area, Rgb565::RED).unwrap();
+        assert_eq!(mock.take(), []);
+        display.flush_dirty_rows().unwrap();
+        mock.take();
+
+        display.set_auto_flush(true);
+        display.fill_solid(&area, Rgb565::GREEN).unwrap();
+        let transfers = mock.take();
+        assert_eq!(mock::commands(&transfers), [0x15, 0x75, 0x5C]);
+        assert_eq!(mock::args(&transfers, 0x15), [[4, 11]]);
+        assert_eq!(mock::ram_data(&transfers).len(), 8 * 8 * 2);
+
+        let colors = (0..16).map(|i| Rgb565::new(i, i, i));
+        let area = Rectangle::new(Point::new(20, 30), Size::new(4, 4));
+        display.fill_contiguous(&area, colors).unwrap();
+        let transfers = mock.take();
+        assert_eq!(mock::commands(&transfers), [0x15, 0x75, 0x5C]);
+        assert_eq!(mock::ram_data(&transfers).len(), 4 * 4 * 2);
+
+        let pixels = [
+            Pixel(Point::new(1, 50), Rgb565::RED),
+            Pixel(Point::new(90, 51), Rgb565::BLUE),
+        ];
+        display.draw_iter(pixels).unwrap();
+        let transfers = mock.take();
+        assert_eq!(mock::commands(&transfers), [0x15, 0x75, 0x5C]);
+        assert_eq!(mock::args(&transfers, 0x75), [[50, 51]]);
+        assert_eq!(mock::ram_data(&transfers).len(), 2 * 128 * 2);
+
+        // Nothing is left over for the next flush
+        display.flush_dirty_rows().unwrap();
+        assert_eq!(mock.take(), []);
+    }
+
+    #[cfg(all(feature = "graphics", feature = "buffered"))]
+    #[test]
+    fn blits_drop_pixels_outside_of_the_clip() {
+        let (mut display, _) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let clip = Rectangle::new(Point::new(10, 10), Size::new(8, 4));
+        display.set_clip(Some(clip));
+
+        let all = display.bounding_box();
+        display.fill_with(&all, |_, _| Rgb565::RED).unwrap();
+        let red = RawU16::from(Rgb565::RED).into_inner();
+        for pos in all.points() {
+            let expected = if clip.contains(pos) { red } else { 0 };
+            assert_eq!(
+                mock::pixel(&display, pos.x as usize, pos.y as usize),
+                expected
+            );
+        }
+
+        let data = [0x07, 0xE0].repeat(32 * 32);
+        let area = Rectangle::new(Point::zero(), Size::new(32, 32));
+        display.draw_raw(&area, &data).unwrap();
+        assert_eq!(mock::pixel(&display, 17, 13), 0x07E0);
+        assert_eq!(mock::pixel(&display, 18, 13), 0);
+        assert_eq!(mock::pixel(&display, 9, 10), 0);
+    }
+
+    #[cfg(all(feature = "graphics", not(feature = "buffered")))]
+    #[test]
+    fn blits_drop_pixels_outside_of_the_clip() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        display.set_clip(Some(Rectangle::new(Point::new(10, 10), Size::new(8, 4))));
+
+        // One run of red covering the top left 32x32 pixels
+        let area = Rectangle::new(Point::zero(), Size::new(32, 32));
+        let rle = [
+            0xFF, 0xF8, 0x00, 0xFF, 0xF8, 0x00, 0xFF, 0xF8, 0x00, 0xFF, 0xF8, 0x00, 4, 0xF8, 0x00,
+        ];
+        display.draw_rle(&area, &rle).unwrap();
+        let transfers = mock.take();
+        assert_eq!(mock::args(&transfers, 0x15), [[10, 17]]);
+        assert_eq!(mock::args(&transfers, 0x75), [[10, 13]]);
+        assert_eq!(mock::ram_data(&transfers), [0xF8, 0x00].repeat(8 * 4));
+
+        display
+            .draw_raw(&area, &[0x07, 0xE0].repeat(32 * 32))
+            .unwrap();
+        let transfers = mock.take();
+        // Same window as before, which the RLE data filled exactly
+        assert_eq!(mock::commands(&transfers), [0x5C]);
+        assert_eq!(mock::ram_data(&transfers), [0x07, 0xE0].repeat(8 * 4));
+
+        display.set_clip(Some(Rectangle::new(Point::new(64, 64), Size::new(4, 4))));
+        display
+            .draw_raw(&area, &[0x07, 0xE0].repeat(32 * 32))
+            .unwrap();
+        assert_eq!(mock.take(), []);
+    }
+
+    #[cfg(feature = "buffered")]
+    #[test]
+    fn failed_flush_keeps_the_dirty_rows() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        display.set_pixel(0, 5, 0xF800);
+
+        mock.set_fail(true);
+        assert!(display.flush().is_err());
+        mock.set_fail(false);
+        display.flush_dirty_rows().unwrap();
+        let transfers = mock.take();
+        assert_eq!(mock::args(&transfers, 0x75), [[5, 5]]);
+        assert_eq!(mock::ram_data(&transfers)[..2], [0xF8, 0x00]);
+
+        // The same through the window of the active rows
+        display.display.set_active_rows(64).unwrap();
+        display.set_pixel(0, 7, 0x001F);
+        mock.set_fail(true);
+        assert!(display.flush().is_err());
+        mock.set_fail(false);
+        mock.take();
+        display.flush_dirty_rows().unwrap();
+        assert_eq!(mock::args(&mock.take(), 0x75), [[7, 7]]);
+    }
+
+    #[cfg(feature = "buffered")]
+    #[test]
+    fn every_flush_only_sends_the_active_rows() {
+        type Flush = fn(&mut GraphicsMode<'static, mock::MockInterface>) -> Result<(), Error>;
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        display.set_active_rows(64).unwrap();
+        mock.take();
+
+        let flushes: [Flush; 5] = [
+            GraphicsMode::flush,
+            GraphicsMode::flush_continuous,
+            GraphicsMode::flush_inverted,
+            GraphicsMode::blank,
+            GraphicsMode::unblank,
+        ];
+        for flush in flushes {
+            display.reset_continuous();
+            display.display.invalidate_draw_area();
+            flush(&mut display).unwrap();
+            let transfers = mock.take();
+            assert_eq!(mock::args(&transfers, 0x15), [[0, 127]]);
+            assert_eq!(mock::args(&transfers, 0x75), [[0, 63]]);
+            assert_eq!(mock::ram_data(&transfers).len(), 128 * 64 * 2);
+        }
+
+        display.display.invalidate_draw_area();
+        let (display, buffer) = display.flush_and_release().unwrap();
+        assert_eq!(mock::ram_data(&mock.take()).len(), 128 * 64 * 2);
+        let mut display = GraphicsMode::new(display, buffer);
+        display.set_rotation(DisplayRotation::Rotate90).unwrap();
+        display.set_tint(255, 0, 255);
+        mock.take();
+        display.flush().unwrap();
+        let transfers = mock.take();
+        assert_eq!(mock::args(&transfers, 0x15), [[0, 127]]);
+        assert_eq!(mock::args(&transfers, 0x75), [[0, 63]]);
+        assert_eq!(mock::ram_data(&transfers).len(), 64 * 128 * 2);
+    }
+
+    #[cfg(feature = "embedded-graphics")]
+    #[test]
+    fn draw_framebuffer_follows_the_byte_order_of_the_framebuffer() {
+        use embedded_graphics::pixelcolor::raw::{BigEndian, LittleEndian};
+
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let mut little = Framebuffer::<Rgb565, RawU16, LittleEndian, 2, 2, 8>::new();
+        let mut big = Framebuffer::<Rgb565, RawU16, BigEndian, 2, 2, 8>::new();
+        little.set_pixel(Point::new(1, 0), Rgb565::RED);
+        little.set_pixel(Point::new(0, 1), Rgb565::BLUE);
+        big.set_pixel(Point::new(1, 0), Rgb565::RED);
+        big.set_pixel(Point::new(0, 1), Rgb565::BLUE);
+        assert_ne!(little.data(), big.data());
+
+        for top_left in [Point::new(10, 20), Point::new(30, 40)] {
+            display.draw_framebuffer(&little, top_left).unwrap();
+            display
+                .draw_framebuffer(&big, top_left + Point::new(2, 0))
+                .unwrap();
+        }
+        #[cfg(not(feature = "buffered"))]
+        {
+            let transfers = mock.take();
+            assert_eq!(mock::args(&transfers, 0x15)[..2], [[10, 11], [12, 13]]);
+            assert_eq!(mock::args(&transfers, 0x75)[..2], [[20, 21], [20, 21]]);
+            let frame = [0, 0, 0xF8, 0x00, 0x00, 0x1F, 0, 0];
+            assert_eq!(mock::ram_data(&transfers), frame.repeat(4));
+        }
+        #[cfg(feature = "buffered")]
+        {
+            assert_eq!(mock.take(), []);
+            for (x, y) in [(10, 20), (12, 20), (30, 40), (32, 40)] {
+                assert_eq!(mock::pixel(&display, x, y), 0);
+                assert_eq!(mock::pixel(&display, x + 1, y), 0xF800);
+                assert_eq!(mock::pixel(&display, x, y + 1), 0x001F);
+            }
+        }
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn mono_bitmap_leaves_the_background_alone_when_transparent() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        // 3 pixels wide, every row padded to a byte: X.X / .X.
+        let glyph = [0b1010_0000, 0b0100_0000];
+        let lit = [(0, 0), (2, 0), (1, 1)];
+        let area = Rectangle::new(Point::new(4, 4), Size::new(3, 2));
+        display.fill_solid(&area, Rgb565::GREEN).unwrap();
+        mock.take();
+
+        display
+            .draw_mono_bitmap(Point::new(4, 4), 3, &glyph, Rgb565::RED, None)
+            .unwrap();
+        #[cfg(not(feature = "buffered"))]
+        {
+            let transfers = mock.take();
+            assert_eq!(mock::ram_data(&transfers), [0xF8, 0x00].repeat(3));
+            assert_eq!(mock::ram_writes(&transfers).len(), 3);
+        }
+        #[cfg(feature = "buffered")]
+        for (x, y) in area.points().map(|p| (p.x as usize - 4, p.y as usize - 4)) {
+            let expected = if lit.contains(&(x, y)) {
+                0xF800
+            } else {
+                0x07E0
+            };
+            assert_eq!(mock::pixel(&display, x + 4, y + 4), expected);
+        }
+
+        display
+            .draw_mono_bitmap(Point::new(4, 4), 3, &glyph, Rgb565::RED, Some(Rgb565::BLUE))
+            .unwrap();
+        let colors: Vec<u16> = area
+            .points()
+            .map(|p| (p.x as usize - 4, p.y as usize - 4))
+            .map(|pos| if lit.contains(&pos) { 0xF800 } else { 0x001F })
+            .collect();
+        #[cfg(not(feature = "buffered"))]
+        {
+            let transfers = mock.take();
+            assert_eq!(mock::ram_writes(&transfers).len(), 1);
+            let data: Vec<_> = colors.iter().flat_map(|c| c.to_be_bytes()).collect();
+            assert_eq!(mock::ram_data(&transfers), data);
+        }
+        #[cfg(feature = "buffered")]
+        for (p, color) in area.points().zip(colors) {
+            assert_eq!(mock::pixel(&display, p.x as usize, p.y as usize), color);
+        }
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn draw_area_rect_programs_the_raw_window() {
+        let rotations = [
+            (DisplayRotation::Rotate0, ((10, 20), (40, 28))),
+            (DisplayRotation::Rotate90, ((20, 10), (28, 40))),
+            (DisplayRotation::Rotate180, ((10, 20), (40, 28))),
+            (DisplayRotation::Rotate270, ((20, 10), (28, 40))),
+        ];
+        for (rotation, (start, end)) in rotations {
+            let (mut display, mock) = mock::graphics(DisplaySize::Display128x128, rotation);
+            let (mut raw, raw_mock) = mock::display(DisplaySize::Display128x128, rotation);
+            let rect = Rectangle::new(Point::new(10, 20), Size::new(30, 8));
+            let area = display.set_draw_area_rect(&rect).unwrap();
+            assert_eq!(raw.set_draw_area(start, end).unwrap(), area);
+            let transfers = mock.take();
+            assert_eq!(mock::args(&transfers, 0x15), [[start.0, end.0 - 1]]);
+            assert_eq!(mock::args(&transfers, 0x75), [[start.1, end.1 - 1]]);
+            assert_eq!(transfers, raw_mock.take());
+        }
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn physical_size_ignores_the_rotation() {
+        for rotation in [DisplayRotation::Rotate0, DisplayRotation::Rotate90] {
+            let (display, _) = mock::graphics(DisplaySize::Display128x96, rotation);
+            assert_eq!(display.physical_size(), Size::new(128, 96));
+        }
+        let (display, _) = mock::graphics(DisplaySize::Display128x96, DisplayRotation::Rotate90);
+        assert_eq!(display.size(), Size::new(96, 128));
+    }
+
+    #[cfg(feature = "buffered")]
+    #[test]
+    fn tint_scales_every_channel_on_the_way_out() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        display.set_pixel(0, 0, 0xFFFF);
+        display.set_pixel(1, 0, 0x07E0);
+        display.set_pixel(2, 0, 0xF81F);
+        display.set_tint(255, 128, 0);
+        display.flush().unwrap();
+        // White keeps its red, loses half the green (63 * 128 / 255 = 31) and all of its blue
+        assert_eq!(
+            mock::ram_data(&mock.take())[..6],
+            [0xFB, 0xE0, 0x03, 0xE0, 0xF8, 0x00]
+        );
+        assert_eq!(mock::pixel(&display, 0, 0), 0xFFFF);
+
+        display.set_tint(255, 255, 255);
+        display.flush().unwrap();
+        assert_eq!(
+            mock::ram_data(&mock.take())[..6],
+            [0xFF, 0xFF, 0x07, 0xE0, 0xF8, 0x1F]
+        );
+    }
+
+    #[cfg(all(feature = "graphics", not(feature = "buffered")))]
+    #[test]
+    fn fill_solid_sends_one_window() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x96, DisplayRotation::Rotate0);
+        let areas = [
+            (display.bounding_box(), [0, 127], [0, 95]),
+            (
+                Rectangle::new(Point::new(10, 20), Size::new(20, 5)),
+                [10, 29],
+                [20, 24],
+            ),
+        ];
+        for (area, columns, rows) in areas {
+            display.fill_solid(&area, Rgb565::RED).unwrap();
+            let transfers = mock.take();
+            assert_eq!(mock::commands(&transfers), [0x15, 0x75, 0x5C]);
+            assert_eq!(mock::args(&transfers, 0x15), [columns]);
+            assert_eq!(mock::args(&transfers, 0x75), [rows]);
+            let data = mock::ram_data(&transfers);
+            assert_eq!(
+                data.len(),
+                area.size.width as usize * area.size.height as usize * 2
+            );
+            assert!(data.chunks_exact(2).all(|pixel| pixel == [0xF8, 0x00]));
+        }
+    }
+
+    #[cfg(feature = "buffered")]
+    #[test]
+    fn frame_hash_only_changes_with_the_framebuffer() {
+        let (mut display, _) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let blank = display.frame_hash();
+        display.set_pixel(200, 3, 0xF800);
+        display.flush().unwrap();
+        assert_eq!(display.frame_hash(), blank);
+
+        display.set_pixel(5, 3, 0xF800);
+        let drawn = display.frame_hash();
+        assert_ne!(drawn, blank);
+        display.set_pixel(5, 3, 0xF800);
+        assert_eq!(display.frame_hash(), drawn);
+
+        display.set_pixel(5, 3, 0x0000);
+        assert_eq!(display.frame_hash(), blank);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn rle_survives_a_round_trip() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let area = Rectangle::new(Point::new(4, 8), Size::new(32, 10));
+        // Long runs of white, then stripes changing every 3 pixels
+        let image: Vec<u16> = (0..320)
+            .map(|i| match i {
+                0..=269 => 0xFFFF,
+                _ => [0xF800, 0x07E0, 0x001F][i / 3 % 3],
+            })
+            .collect();
+        let mut rle = Vec::new();
+        for run in image.chunk_by(|a, b| a == b) {
+            for part in run.chunks(255) {
+                rle.push(part.len() as u8);
+                rle.extend(part[0].to_be_bytes());
+            }
+        }
+        assert!(rle.len() < image.len());
+
+        display.draw_rle(&area, &rle).unwrap();
+        #[cfg(not(feature = "buffered"))]
+        {
+            let data: Vec<_> = image.iter().flat_map(|c| c.to_be_bytes()).collect();
+            assert_eq!(mock::ram_data(&mock.take()), data);
+        }
+        #[cfg(feature = "buffered")]
+        {
+            assert_eq!(mock.take(), []);
+            for (pos, &color) in area.points().zip(&image) {
+                assert_eq!(mock::pixel(&display, pos.x as usize, pos.y as usize), color);
+            }
+        }
+
+        // Runs adding up to a pixel too few or too many, a zero run and a partial run
+        let short = [&rle[..rle.len() - 3], &[1, 0x07, 0xE0]].concat();
+        let long = [&rle[..], &[1, 0x00, 0x1F]].concat();
+        let zero = [&rle[..], &[0, 0x00, 0x1F]].concat();
+        let partial = [&rle[..], &[1, 0x00]].concat();
+        for bad in [short, long, zero, partial] {
+            assert!(matches!(
+                display.draw_rle(&area, &bad),
+                Err(Error::InvalidData)
+            ));
+        }
+        assert_eq!(mock.take(), []);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn fill_with_draws_a_gradient() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let area = Rectangle::new(Point::new(10, 5), Size::new(32, 2));
+        display
+            .fill_with(&area, |x, _| Rgb565::new((x - 10) as u8, 0, 0))
+            .unwrap();
+
+        #[cfg(not(feature = "buffered"))]
+        let row: Vec<u16> = mock::ram_data(&mock.take())
+            .chunks_exact(2)
+            .take(32)
+            .map(|pixel| u16::from_be_bytes([pixel[0], pixel[1]]))
+            .collect();
+        #[cfg(feature = "buffered")]
+        let row: Vec<u16> = (10..42).map(|x| mock::pixel(&display, x, 6)).collect();
+        #[cfg(feature = "buffered")]
+        assert_eq!(mock.take(), []);
+
+        assert_eq!(row[0], 0x0000);
+        assert_eq!(row[31], 0xF800);
+        assert!(row.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(row.iter().all(|color| color & 0x07FF == 0));
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn blit_scaled_doubles_every_pixel() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let palette = [
+            0x0000, 0xF800, 0x07E0, 0x001F, 0xFFFF, 0xFFE0, 0xF81F, 0x07FF,
+        ];
+        let src: Vec<u16> = (0..16).map(|i| palette[(i * 3) % 8]).collect();
+        let data: Vec<u8> = src.iter().flat_map(|c| c.to_be_bytes()).collect();
+        let dst = Rectangle::new(Point::new(20, 30), Size::new(8, 8));
+        display.blit_scaled(&data, Size::new(4, 4), &dst).unwrap();
+
+        // Row by row, every source pixel turns into a 2x2 block
+        let expected: Vec<u16> = dst
+            .points()
+            .map(|p| src[(p.y as usize - 30) / 2 * 4 + (p.x as usize - 20) / 2])
+            .collect();
+        #[cfg(not(feature = "buffered"))]
+        {
+            let transfers = mock.take();
+            assert_eq!(mock::args(&transfers, 0x15), [[20, 27]]);
+            assert_eq!(mock::args(&transfers, 0x75), [[30, 37]]);
+            let sent: Vec<_> = expected.iter().flat_map(|c| c.to_be_bytes()).collect();
+            assert_eq!(mock::ram_data(&transfers), sent);
+        }
+        #[cfg(feature = "buffered")]
+        {
+            assert_eq!(mock.take(), []);
+            for (p, color) in dst.points().zip(expected) {
+                assert_eq!(mock::pixel(&display, p.x as usize, p.y as usize), color);
+            }
+        }
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn progress_bar_fills_the_clamped_fraction() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let area = Rectangle::new(Point::new(10, 10), Size::new(40, 4));
+        for (fraction, filled) in [(0.0, 0), (0.5, 20), (1.0, 40), (1.5, 40)] {
+            display
+                .draw_progress_bar(&area, fraction, Rgb565::GREEN, Rgb565::BLUE)
+                .unwrap();
+            #[cfg(not(feature = "buffered"))]
+            {
+                display.display.invalidate_draw_area();
+                let transfers = mock.take();
+                let mut columns = Vec::new();
+                if filled > 0 {
+                    columns.push([10, 9 + filled]);
+                }
+                if filled < 40 {
+                    columns.push([10 + filled, 49]);
+                }
+                assert_eq!(mock::args(&transfers, 0x15), columns);
+                let data = mock::ram_data(&transfers);
+                assert_eq!(data.len(), 40 * 4 * 2);
+                let green = data.chunks_exact(2).filter(|p| p == &[0x07, 0xE0]).count();
+                assert_eq!(green, filled as usize * 4);
+            }
+            #[cfg(feature = "buffered")]
+            for p in area.points() {
+                let expected = if p.x < 10 + filled { 0x07E0 } else { 0x001F };
+                assert_eq!(mock::pixel(&display, p.x as usize, p.y as usize), expected);
+            }
+        }
+        #[cfg(feature = "buffered")]
+        assert_eq!(mock.take(), []);
+    }
+
+    /// The pixels a shape drawn by `draw` lights up, from the single row windows it sent or from
+    /// the framebuffer
+    fn drawn_pixels(
+        draw: impl FnOnce(&mut GraphicsMode<'static, mock::MockInterface>),
+    ) -> std::collections::BTreeSet<(i32, i32)> {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        draw(&mut display);
+        let transfers = mock.take();
+        #[cfg(not(feature = "buffered"))]
+        {
+            // Follow the window through the transfers, a repeated one is only sent once
+            let (mut window, mut last) = ([[0; 2]; 2], 0);
+            let mut pixels = std::collections::BTreeSet::new();
+            for transfer in &transfers {
+                match transfer {
+                    mock::Transfer::Command(bytes) => last = *bytes.last().unwrap(),
+                    mock::Transfer::Data(bytes) if last == 0x15 => window[0] = [bytes[0], bytes[1]],
+                    mock::Transfer::Data(bytes) if last == 0x75 => window[1] = [bytes[0], bytes[1]],
+                    mock::Transfer::Data(bytes) if last == 0x5C => {
+                        let [[x0, x1], [y0, y1]] = window;
+                        // Every run is a single row
+                        assert_eq!(y0, y1);
+                        assert_eq!(bytes.len(), (x1 - x0 + 1) as usize * 2);
+                        pixels.extend((x0..=x1).map(|x| (x as i32, y0 as i32)));
+                    }
+                    mock::Transfer::Data(_) => {}
+                }
+            }
+            pixels
+        }
+        #[cfg(feature = "buffered")]
+        {
+            assert_eq!(transfers, []);
+            (0..128)
+                .flat_map(|y| (0..128).map(move |x| (x, y)))
+                .filter(|&(x, y)| mock::pixel(&display, x as usize, y as usize) != 0)
+                .collect()
+        }
+    }
+
+    #[cfg(feature = "graphics")]
+    /// The pixels of `area` with corners of radius `r` rounded off, x² + y² <= r² + r from the
+    /// nearest corner centre
+    fn rounded_rect_expected(area: &Rectangle, r: i32) -> std::collections::BTreeSet<(i32, i32)> {
+        let (x0, y0) = (area.top_left.x, area.top_left.y);
+        let (x1, y1) = (
+            x0 + area.size.width as i32 - 1,
+            y0 + area.size.height as i32 - 1,
+        );
+        area.points()
+            .map(|p| (p.x, p.y))
+            .filter(|&(x, y)| {
+                let dx = x - x.clamp(x0 + r, x1 - r);
+                let dy = y - y.clamp(y0 + r, y1 - r);
+                dx * dx + dy * dy <= r * r + r
+            })
+            .filter(|&(x, y)| (0..128).contains(&x) && (0..128).contains(&y))
+            .collect()
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn rounded_rects_handle_typical_and_degenerate_radii() {
+        let area = Rectangle::new(Point::new(10, 20), Size::new(20, 12));
+        let fill = |area: Rectangle, radius| {
+            drawn_pixels(|d| d.fill_rounded_rect(&area, radius, Rgb565::RED).unwrap())
+        };
+        let outline = |area: Rectangle, radius| {
+            drawn_pixels(|d| d.draw_rounded_rect(&area, radius, Rgb565::RED).unwrap())
+        };
+
+        // A typical radius cuts the corners and leaves the straight edges whole
+        let filled = fill(area, 4);
+        assert_eq!(filled, rounded_rect_expected(&area, 4));
+        assert!(!filled.contains(&(10, 20)) && !filled.contains(&(29, 31)));
+        let framed = outline(area, 4);
+        assert!(framed.is_subset(&filled));
+        assert!((14..=25).all(|x| framed.contains(&(x, 20)) && framed.contains(&(x, 31))));
+        assert!((24..=27).all(|y| framed.contains(&(10, y)) && framed.contains(&(29, y))));
+        assert!(!framed.contains(&(10, 20)) && !framed.contains(&(20, 25)));
+
+        // Radius 0 is the plain rectangle and its border
+        let square = rounded_rect_expected(&area, 0);
+        assert_eq!(fill(area, 0), square);
+        let border: std::collections::BTreeSet<_> = square
+            .iter()
+            .copied()
+            .filter(|&(x, y)| x == 10 || x == 29 || y == 20 || y == 31)
+            .collect();
+        assert_eq!(outline(area, 0), border);
+
+        // A radius beyond half the smaller side is reduced to it, a square becomes a circle
+        assert_eq!(fill(area, 100), fill(area, 5));
+        assert_eq!(outline(area, 100), outline(area, 5));
+        let square = Rectangle::new(Point::new(40, 40), Size::new(15, 15));
+        assert_eq!(fill(square, 100), rounded_rect_expected(&square, 7));
+        assert!(outline(square, 100).is_subset(&fill(square, 100)));
+
+        // Shapes are clipped to the display
+        let corner = Rectangle::new(Point::new(120, -4), Size::new(20, 12));
+        let clipped = fill(corner, 4);
+        assert_eq!(clipped, rounded_rect_expected(&corner, 4));
+        assert!(!clipped.is_empty());
+        assert!(outline(corner, 4).iter().all(|&(x, y)| x < 128 && y >= 0));
+        assert!(fill(Rectangle::new(Point::new(200, 10), Size::new(20, 12)), 4).is_empty());
+    }
+
+    #[test]
+    fn circles_are_clipped_at_the_edge_and_skipped_off_the_display() {
+        let fill = |cx, cy| drawn_pixels(|d| d.fill_circle(cx, cy, 10, 0xF800).unwrap());
+        let outline = |cx, cy| drawn_pixels(|d| d.draw_circle(cx, cy, 10, 0xF800).unwrap());
+        let shift = |pixels: std::collections::BTreeSet<(i32, i32)>| {
+            pixels
+                .into_iter()
+                .map(|(x, y)| (x - 62, y))
+                .filter(|&(x, _)| x >= 0)
+                .collect::<std::collections::BTreeSet<_>>()
+        };
+
+        // Near the left edge only the part on the display is drawn, every pixel of it
+        let filled = fill(2, 64);
+        let expected = (54..=74)
+            .flat_map(|y| (0..=12).map(move |x| (x, y)))
+            .filter(|&(x, y): &(i32, i32)| (x - 2).pow(2) + (y - 64).pow(2) <= 110)
+            .collect();
+        assert_eq!(filled, expected);
+        assert_eq!(filled, shift(fill(64, 64)));
+        let framed = outline(2, 64);
+        assert_eq!(framed, shift(outline(64, 64)));
+        assert!(framed.is_subset(&filled));
+        assert!([(12, 64), (2, 54), (2, 74)]
+            .iter()
+            .all(|p| framed.contains(p)));
+        assert!(!framed.contains(&(2, 64)));
+
+        // Completely off the display nothing is sent or drawn
+        for (cx, cy) in [(-20, -20), (64, 200), (139, 64)] {
+            assert!(fill(cx, cy).is_empty());
+            assert!(outline(cx, cy).is_empty());
+        }
+    }
+
+    #[cfg(all(feature = "graphics", feature = "buffered"))]
+    #[test]
+    fn invert_region_only_inverts_inside_the_clipped_area() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        for y in 0..128 {
+            for x in 0..128 {
+                display.set_pixel(x, y, if (x + y) % 2 == 0 { 0xF800 } else { 0x07E0 });
+            }
+        }
+        display.flush().unwrap();
+        let before = display.fb().to_vec();
+        mock.take();
+
+        let area = Rectangle::new(Point::new(120, 4), Size::new(20, 3));
+        display.invert_region(&area);
+        assert_eq!(mock.take(), []);
+        for y in 0..128 {
+            for x in 0..128 {
+                let original = if (x + y) % 2 == 0 { 0xF800 } else { 0x07E0 };
+                let inside = x >= 120 && (4..7).contains(&y);
+                let expected = if inside { original ^ 0xFFFF } else { original };
+                assert_eq!(mock::pixel(&display, x, y), expected);
+            }
+        }
+
+        // Only the rows of the region are dirty, and a second pass restores the framebuffer
+        display.flush_dirty_rows().unwrap();
+        assert_eq!(mock::args(&mock.take(), 0x75), [[4, 6]]);
+        display.invert_region(&area);
+        assert_eq!(display.fb(), before);
+    }
+
+    #[cfg(feature = "buffered")]
+    #[test]
+    fn mono_mode_sends_gray_and_dims_the_contrast() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        display.set_pixel(0, 0, 0xFFFF);
+        display.set_pixel(1, 0, 0xF800);
+        display.set_pixel(2, 0, 0x07E0);
+        display.mono_mode(true).unwrap();
+        assert_eq!(mock::args(&mock.take(), 0xC7), [[MONO_CONTRAST]]);
+        display.flush().unwrap();
+        assert_eq!(
+            mock::ram_data(&mock.take())[..6],
+            [0xFF, 0xFF, 0x4A, 0x49, 0x94, 0x92]
+        );
+        assert_eq!(mock::pixel(&display, 1, 0), 0xF800);
+
+        // Off again restores the contrast and the colours
+        display.mono_mode(false).unwrap();
+        assert_eq!(mock::args(&mock.take(), 0xC7), [[0x0F]]);
+        display.flush().unwrap();
+        assert_eq!(
+            mock::ram_data(&mock.take())[..6],
+            [0xFF, 0xFF, 0xF8, 0x00, 0x07, 0xE0]
+        );
+    }
+
+    #[cfg(all(feature = "graphics", not(feature = "palette8")))]
+    #[test]
+    fn dither_spreads_a_color_between_rgb565_steps() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let area = Rectangle::new(Point::new(0, 0), Size::new(4, 4));
+        // Half a step above black in every channel
+        let mut capture = |dither| {
+            display.set_dither(dither);
+            display
+                .fill_with_rgb888(&area, |_, _| Rgb888::new(4, 2, 4))
+                .unwrap();
+            #[cfg(not(feature = "buffered"))]
+            let pixels: Vec<u16> = mock::ram_data(&mock.take())
+                .chunks_exact(2)
+                .map(|p| u16::from_be_bytes([p[0], p[1]]))
+                .collect();
+            #[cfg(feature = "buffered")]
+            let pixels: Vec<u16> = area
+                .points()
+                .map(|p| mock::pixel(&display, p.x as usize, p.y as usize))
+                .collect();
+            pixels
+        };
+
+        assert_eq!(capture(false), [0x0000; 16]);
+        // The pixels with a Bayer threshold of 8 or more step up by one in every channel
+        assert_eq!(
+            capture(true),
+            [
+                0x0000, 0x0821, 0x0000, 0x0821, //
+                0x0821, 0x0000, 0x0821, 0x0000, //
+                0x0000, 0x0821, 0x0000, 0x0821, //
+                0x0821, 0x0000, 0x0821, 0x0000,
+            ]
+        );
+        #[cfg(feature = "buffered")]
+        assert_eq!(mock.take(), []);
+    }
+
+    #[cfg(all(feature = "graphics", not(feature = "buffered")))]
+    #[test]
+    fn fill_solid_with_scratch_chunks_by_the_scratch_size() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let area = Rectangle::new(Point::new(10, 20), Size::new(10, 5));
+        let mut fill = |scratch: &mut [u8]| {
+            display.display.invalidate_draw_area();
+            display
+                .fill_solid_with_scratch(&area, Rgb565::RED, scratch)
+                .unwrap();
+            let transfers = mock.take();
+            assert_eq!(mock::args(&transfers, 0x15), [[10, 19]]);
+            assert_eq!(mock::args(&transfers, 0x75), [[20, 24]]);
+            mock::ram_writes(&transfers)
+        };
+
+        // A single pixel of scratch sends the same bytes, one pixel per transfer
+        let minimal = fill(&mut [0; 2]);
+        let large = fill(&mut [0; 64]);
+        assert_eq!(minimal.len(), 50);
+        assert_eq!(large.len(), 2);
+        assert_eq!(minimal.concat(), large.concat());
+        assert_eq!(minimal.concat(), [0xF8, 0x00].repeat(50));
+        // An odd byte at the end is left unused
+        assert!(fill(&mut [0; 3]).iter().all(|write| write.len() == 2));
+
+        assert!(matches!(
+            display.fill_solid_with_scratch(&area, Rgb565::RED, &mut [0; 1]),
+            Err(Error::BufferSize)
+        ));
+        assert_eq!(mock.take(), []);
+    }
+
+    #[cfg(all(feature = "graphics", not(feature = "palette8")))]
+    #[test]
+    fn draw_raw_with_order_swaps_little_endian_data_only() {
+        let data = [0xF8, 0x00, 0x00, 0x1F, 0x07, 0xE0, 0xFF, 0xFF];
+        let swapped = [0x00, 0xF8, 0x1F, 0x00, 0xE0, 0x07, 0xFF, 0xFF];
+        // The order of the framebuffer and of `draw_raw` does not matter for the explicit one
+        for buffer_order in [ByteOrder::BigEndian, ByteOrder::LittleEndian] {
+            for (order, sent) in [
+                (ByteOrder::BigEndian, data),
+                (ByteOrder::LittleEndian, swapped),
+            ] {
+                let (mut display, mock) =
+                    mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+                display.set_byte_order(buffer_order);
+                let area = Rectangle::new(Point::new(0, 0), Size::new(2, 2));
+                display.draw_raw_with_order(&area, &data, order).unwrap();
+                // Half off the display only the visible column is sent
+                let area = Rectangle::new(Point::new(127, 2), Size::new(2, 2));
+                display.draw_raw_with_order(&area, &data, order).unwrap();
+                #[cfg(feature = "buffered")]
+                {
+                    assert_eq!(mock.take(), []);
+                    display.flush().unwrap();
+                    let frame = mock::ram_data(&mock.take());
+                    assert_eq!(frame[..4], sent[..4]);
+                    assert_eq!(frame[256..260], sent[4..]);
+                    assert_eq!(frame[2 * 256 + 254..2 * 256 + 256], sent[..2]);
+                    assert_eq!(frame[3 * 256 + 254..3 * 256 + 256], sent[4..6]);
+                }
+                #[cfg(not(feature = "buffered"))]
+                {
+                    let transfers = mock.take();
+                    assert_eq!(mock::args(&transfers, 0x15), [[0, 1], [127, 127]]);
+                    let ram = mock::ram_data(&transfers);
+                    assert_eq!(ram, [&sent[..], &sent[..2], &sent[4..6]].concat());
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "buffered")]
+    #[test]
+    fn a_taken_buffer_keeps_its_content_until_restored() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        display.set_pixel(3, 2, 0xF800);
+        display.set_pixel(127, 127, 0x07E0);
+        display.flush().unwrap();
+        mock.take();
+        let frame = display.fb().to_vec();
+
+        let buffer = display.take_buffer().unwrap();
+        assert_eq!(buffer, frame);
+
+        // A buffer of the wrong length comes back untouched, the display stays without one
+        let short = vec![0xAA; 100].leak();
+        let short = display.restore_buffer(short).unwrap_err();
+        assert_eq!(short.len(), 100);
+        assert!(short.iter().all(|&byte| byte == 0xAA));
+        assert!(!display.has_buffer());
+
+        display.restore_buffer(buffer).unwrap();
+        assert_eq!(display.fb(), frame);
+        assert_eq!(mock::pixel(&display, 3, 2), 0xF800);
+        assert_eq!(mock::pixel(&display, 127, 127), 0x07E0);
+        // The restored content is sent in full, as the display may have shown anything meanwhile
+        display.flush_dirty_rows().unwrap();
+        let sent = mock::ram_data(&mock.take());
+        assert_eq!(sent.len(), 128 * 128 * 2);
+        assert_eq!(sent[(2 * 128 + 3) * 2..][..2], [0xF8, 0x00]);
+        assert_eq!(sent[sent.len() - 2..], [0x07, 0xE0]);
+    }
+
+    #[cfg(feature = "buffered")]
+    #[test]
+    fn flush_yielding_yields_between_bands() {
+        use core::future::poll_fn;
+        use core::task::Poll;
+        use std::cell::Cell;
+
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let yields = Cell::new(0);
+        // Pending once, like `yield_now`
+        let yield_now = || {
+            yields.set(yields.get() + 1);
+            let mut yielded = false;
+            poll_fn(move |_| {
+                if core::mem::replace(&mut yielded, true) {
+                    Poll::Ready(())
+                } else {
+                    Poll::Pending
+                }
+            })
+        };
+        {
+            let mut flush = core::pin::pin!(display.flush_yielding(yield_now));
+            for band in 0..8u8 {
+                let poll = mock::poll_once(flush.as_mut());
+                assert_eq!(matches!(poll, Poll::Ready(Ok(()))), band == 7);
+                let transfers = mock.take();
+                assert_eq!(mock::args(&transfers, 0x75), [[band * 16, band * 16 + 15]]);
+                assert_eq!(mock::ram_data(&transfers).len(), 128 * 16 * 2);
+            }
+        }
+        assert_eq!(yields.get(), 7);
+
+        // 96 rows are 6 bands
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x96, DisplayRotation::Rotate0);
+        yields.set(0);
+        mock::block_on(display.flush_yielding(yield_now)).unwrap();
+        assert_eq!(yields.get(), 5);
+        assert_eq!(mock::args(&mock.take(), 0x75).len(), 6);
+
+        display.take_buffer().unwrap();
+        yields.set(0);
+        assert!(matches!(
+            mock::block_on(display.flush_yielding(yield_now)),
+            Err(Error::BufferSize)
+        ));
+        assert_eq!(yields.get(), 0);
+        assert_eq!(mock.take(), []);
+    }
+
+    #[cfg(all(feature = "graphics", feature = "buffered"))]
+    #[test]
+    fn flushing_in_preferred_chunks_sends_contiguous_bands() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        for y in 0..128 {
+            display.set_pixel(y, y, 0xF800);
+        }
+        display.flush().unwrap();
+        let frame = mock::ram_data(&mock.take());
+
+        let chunk = display.preferred_chunk_size();
+        assert_eq!(chunk, Size::new(128, 8));
+        let mut sent = Vec::new();
+        for band in 0..128 / chunk.height as u8 {
+            let y = band * chunk.height as u8;
+            display
+                .flush_area(Region {
+                    x: 0,
+                    y,
+                    width: chunk.width as u8,
+                    height: chunk.height as u8,
+                })
+                .unwrap();
+            // One window and 2 KiB of data per band
+            let transfers = mock.take();
+            assert_eq!(mock::args(&transfers, 0x15), [[0, 127]]);
+            assert_eq!(mock::args(&transfers, 0x75), [[y, y + 7]]);
+            let data = mock::ram_data(&transfers);
+            assert_eq!(data.len(), 2048);
+            sent.extend_from_slice(&data);
+        }
+        assert_eq!(sent, frame);
+
+        // Bands follow the orientation and never exceed the height
+        let (display, _) = mock::graphics(DisplaySize::Display128x96, DisplayRotation::Rotate90);
+        assert_eq!(display.preferred_chunk_size(), Size::new(96, 8));
+        let (mut display, _) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        display.set_active_rows(4).unwrap();
+        assert_eq!(display.preferred_chunk_size(), Size::new(128, 4));
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn draw_target_clear_takes_the_bulk_paths() {
+        let size = DisplaySize::Display128x128;
+        for (color, raw) in [(Rgb565::BLACK, 0x0000), (Rgb565::RED, 0xF800)] {
+            let (mut display, mock) = mock::graphics(size, DisplayRotation::Rotate0);
+            let (mut reference, reference_mock) = mock::graphics(size, DisplayRotation::Rotate0);
+            #[cfg(feature = "buffered")]
+            display.set_pixel(5, 5, 0x07E0);
+            DrawTarget::clear(&mut display, color).unwrap();
+
+            // The same transfers as the inherent bulk clear
+            #[cfg(not(feature = "buffered"))]
+            {
+                if color == Rgb565::BLACK {
+                    reference.clear().unwrap();
+                } else {
+                    reference.clear_to(raw).unwrap();
+                }
+                let transfers = mock.take();
+                assert_eq!(transfers, reference_mock.take());
+                let data = mock::ram_data(&transfers);
+                assert_eq!(data, raw.to_be_bytes().repeat(128 * 128));
+            }
+            // Only the framebuffer changes without auto flush, with it the frame is sent
+            #[cfg(feature = "buffered")]
+            {
+                assert_eq!(mock.take(), []);
+                assert!((0..128).all(|i| mock::pixel(&display, i, i) == raw));
+                reference.set_auto_flush(true);
+                DrawTarget::clear(&mut reference, color).unwrap();
+                let data = mock::ram_data(&reference_mock.take());
+                assert_eq!(data, raw.to_be_bytes().repeat(128 * 128));
+            }
+        }
+
+        // A clip rectangle limits the clear to it
+        let (mut display, mock) = mock::graphics(size, DisplayRotation::Rotate0);
+        let clip = Rectangle::new(Point::new(10, 10), Size::new(4, 2));
+        display.set_clip(Some(clip));
+        DrawTarget::clear(&mut display, Rgb565::RED).unwrap();
+        #[cfg(not(feature = "buffered"))]
+        {
+            let transfers = mock.take();
+            assert_eq!(mock::args(&transfers, 0x15), [[10, 13]]);
+            assert_eq!(mock::ram_data(&transfers), [0xF8, 0x00].repeat(8));
+        }
+        #[cfg(feature = "buffered")]
+        {
+            assert_eq!(mock.take(), []);
+            assert_eq!(mock::pixel(&display, 10, 10), 0xF800);
+            assert_eq!(mock::pixel(&display, 13, 11), 0xF800);
+            assert_eq!(mock::pixel(&display, 9, 10), 0);
+            assert_eq!(mock::pixel(&display, 14, 11), 0);
+        }
+    }
+
+    #[cfg(feature = "buffered")]
+    #[test]
+    fn blank_and_unblank_leave_the_framebuffer_alone() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        display.set_pixel(0, 0, 0xFFFF);
+        display.set_pixel(1, 0, 0xF800);
+        display.set_pixel(127, 63, 0x07E0);
+        let frame = display.fb().to_vec();
+        display.set_active_rows(64).unwrap();
+        mock.take();
+
+        display.blank().unwrap();
+        assert_eq!(display.fb(), frame);
+        let transfers = mock.take();
+        // Only the active rows are sent, darkened
+        assert_eq!(mock::args(&transfers, 0x75), [[0, 63]]);
+        let data = mock::ram_data(&transfers);
+        assert_eq!(data.len(), 128 * 64 * 2);
+        // A quarter through a tint of 64 out of 255, rounded down per channel
+        assert_eq!(data[..4], [0x39, 0xE7, 0x38, 0x00]);
+        assert_eq!(data[data.len() - 2..], [0x01, 0xE0]);
+
+        display.unblank().unwrap();
+        assert_eq!(display.fb(), frame);
+        let data = mock::ram_data(&mock.take());
+        assert_eq!(data.len(), 128 * 64 * 2);
+        assert_eq!(data[..4], [0xFF, 0xFF, 0xF8, 0x00]);
+        assert_eq!(data[data.len() - 2..], [0x07, 0xE0]);
     }
 }
@@ -5,32 +5,39 @@
 //! [`GraphicsMode`](../graphics/index.html) and [`TerminalMode`](../terminal/index.html).
 
 use crate::display::Display;
+#[cfg(not(feature = "buffered"))]
+use core::marker::PhantomData;
 use display_interface::WriteOnlyDataCommand;
 
 use crate::mode::displaymode::DisplayModeTrait;
 
 /// Raw display mode
-pub struct RawMode<DI>
+pub struct RawMode<'a, DI>
 where
     DI: WriteOnlyDataCommand,
 {
     pub display: Display<DI>,
     #[cfg(feature = "buffered")]
-    pub buffer: &'static mut [u8],
+    pub buffer: &'a mut [u8],
+    #[cfg(not(feature = "buffered"))]
+    buffer: PhantomData<&'a mut [u8]>,
 }
 
-impl<DI> DisplayModeTrait<DI> for RawMode<DI>
+impl<'a, DI> DisplayModeTrait<'a, DI> for RawMode<'a, DI>
 where
     DI: WriteOnlyDataCommand,
 {
     /// Create new RawMode instance
     #[cfg(not(feature = "buffered"))]
     fn new(display: Display<DI>) -> Self {
-        RawMode { display }
+        RawMode {
+            display,
+            buffer: PhantomData,
+        }
     }
 
     #[cfg(feature = "buffered")]
-    fn new(display: Display<DI>, buffer: &'static mut [u8]) -> Self {
+    fn new(display: Display<DI>, buffer: &'a mut [u8]) -> Self {
         RawMode { display, buffer }
     }
 
@@ -42,7 +49,7 @@ where
 
     #[cfg(feature = "buffered")]
     /// Release all resources used by RawMode
-    fn release(self) -> (Display<DI>, &'static mut [u8]) {
+    fn release(self) -> (Display<DI>, &'a mut [u8]) {
         (self.display, self.buffer)
     }
 }
@@ -0,0 +1,105 @@
+//! Several displays driven together
+//!
+//! Panels on a shared SPI bus each need their own chip select line. Give every panel its own
+//! `SpiDevice` (e.g. from `embedded-hal-bus` or `embassy-embedded-hal`) wrapped in its own
+//! `SPIInterface`: the device takes care of asserting CS around every transfer, so each
+//! [`Display`](crate::display::Display) only ever talks to its own panel. Every panel keeps its own
+//! rotation and, with the `buffered` feature, its own framebuffer.
+
+use display_interface::WriteOnlyDataCommand;
+
+use crate::error::Error;
+use crate::mode::GraphicsMode;
+
+/// A fixed set of displays of the same interface type, e.g. the tiles of a video wall
+pub struct MultiDisplay<'a, DI, const N: usize>
+where
+    DI: WriteOnlyDataCommand,
+{
+    displays: [GraphicsMode<'a, DI>; N],
+}
+
+impl<'a, DI, const N: usize> MultiDisplay<'a, DI, N>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Create a new MultiDisplay from its displays
+    pub fn new(displays: [GraphicsMode<'a, DI>; N]) -> Self {
+        MultiDisplay { displays }
+    }
+
+    /// Release the displays
+    pub fn release(self) -> [GraphicsMode<'a, DI>; N] {
+        self.displays
+    }
+
+    /// Access the displays, e.g. to draw on one of them
+    pub fn displays(&mut self) -> &mut [GraphicsMode<'a, DI>; N] {
+        &mut self.displays
+    }
+
+    /// Initialise every display in turn, stopping at the first error
+    pub fn init_all(&mut self) -> Result<(), Error> {
+        self.displays
+            .iter_mut()
+            .try_for_each(|display| display.init())
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Flush the framebuffer of every display in turn through
+    /// [`flush_continuous`](GraphicsMode::flush_continuous), stopping at the first error
+    pub fn flush_all(&mut self) -> Result<(), Error> {
+        self.displays
+            .iter_mut()
+            .try_for_each(|display| display.flush_continuous())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+    use crate::properties::{DisplayRotation, DisplaySize};
+
+    #[test]
+    fn every_panel_only_sees_its_own_transfers() {
+        let (first, first_mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let (second, second_mock) =
+            mock::graphics(DisplaySize::Display128x96, DisplayRotation::Rotate180);
+        let mut multi = MultiDisplay::new([first, second]);
+
+        let (mut lone, lone_mock) =
+            mock::graphics(DisplaySize::Display128x96, DisplayRotation::Rotate180);
+        lone.init().unwrap();
+        multi.init_all().unwrap();
+        assert!(!first_mock.take().is_empty());
+        assert_eq!(second_mock.take(), lone_mock.take());
+
+        multi.displays()[1].set_pixels(&[(3, 4, 0xF800)]).unwrap();
+        multi.displays()[0].set_pixels(&[(5, 6, 0x001F)]).unwrap();
+        #[cfg(not(feature = "buffered"))]
+        {
+            assert_eq!(mock::ram_data(&first_mock.take()), [0x00, 0x1F]);
+            assert_eq!(mock::ram_data(&second_mock.take()), [0xF8, 0x00]);
+        }
+        #[cfg(feature = "buffered")]
+        {
+            assert_eq!(first_mock.take(), []);
+            assert_eq!(second_mock.take(), []);
+            multi.flush_all().unwrap();
+            let first_data = mock::ram_data(&first_mock.take());
+            let second_data = mock::ram_data(&second_mock.take());
+            assert_eq!(first_data.len(), 128 * 128 * 2);
+            assert_eq!(second_data.len(), 128 * 96 * 2);
+            assert_eq!(first_data.iter().filter(|&&byte| byte != 0).count(), 1);
+            assert_eq!(second_data.iter().filter(|&&byte| byte != 0).count(), 1);
+            assert!(first_data.contains(&0x1F) && second_data.contains(&0xF8));
+        }
+
+        // A failing panel stops the others from being touched
+        first_mock.set_fail(true);
+        assert!(multi.init_all().is_err());
+        assert_eq!(second_mock.take(), []);
+    }
+}
@@ -0,0 +1,153 @@
+//! Software frame pacing
+//!
+//! The SSD1351 has no frame sync output, so the frame boundaries can only be estimated from the
+//! configured timing, see [`Display::frame_period_ns`](crate::display::Display::frame_period_ns).
+//! This is purely timing based and will drift from the real refresh over time, but calling
+//! `flush` at the display's own frame rate still reduces visible tearing in periodic animations.
+//! [`FrameLimiter`] instead caps the frame rate below that to save bus bandwidth.
+
+use hal::delay::DelayNs;
+
+/// Paces drawing to the estimated frame rate of the display
+pub struct FramePacer {
+    frame_period_ns: u32,
+}
+
+impl FramePacer {
+    /// Create a new pacer for the given frame period
+    pub fn new(frame_period_ns: u32) -> Self {
+        Self {
+            frame_period_ns: frame_period_ns.max(1),
+        }
+    }
+
+    /// Get the frame period in nanoseconds
+    pub fn frame_period_ns(&self) -> u32 {
+        self.frame_period_ns
+    }
+
+    /// Sleep until the next estimated frame boundary. `elapsed_ns` is the time spent since the
+    /// previous call returned (e.g. rendering the next frame), as measured by the caller. Pass `0`
+    /// if it is not known to wait for a full frame period.
+    pub fn wait<DELAY>(&self, delay: &mut DELAY, elapsed_ns: u32)
+    where
+        DELAY: DelayNs,
+    {
+        delay.delay_ns(self.frame_period_ns - elapsed_ns % self.frame_period_ns);
+    }
+}
+
+/// Caps the rate of flushes, e.g. of an animation loop that renders faster than is worth sending
+/// over the bus. Time is taken in microseconds from a free running timer and may wrap around.
+pub struct FrameLimiter {
+    min_interval_us: u32,
+    last_frame_us: Option<u32>,
+    dropped: u32,
+}
+
+impl FrameLimiter {
+    /// Create a new limiter letting through at most `fps` frames per second
+    pub fn new(fps: u32) -> Self {
+        Self {
+            min_interval_us: 1_000_000 / fps.max(1),
+            last_frame_us: None,
+            dropped: 0,
+        }
+    }
+
+    /// Get the shortest time between two frames in microseconds
+    pub fn min_interval_us(&self) -> u32 {
+        self.min_interval_us
+    }
+
+    /// Get the number of frames dropped by [`limit`](Self::limit) so far
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    /// Whether a frame may be sent at `now_us`. A `true` starts the next interval, so call this
+    /// only when the frame will actually be sent.
+    pub fn ready(&mut self, now_us: u32) -> bool {
+        let ready = self
+            .last_frame_us
+            .is_none_or(|last| now_us.wrapping_sub(last) >= self.min_interval_us);
+        if ready {
+            self.last_frame_us = Some(now_us);
+        }
+        ready
+    }
+
+    /// Run `flush` if a frame may be sent at `now_us` and return its result, or drop the frame
+    /// and return `None`, e.g. `limiter.limit(now_us, || display.flush_continuous())`. The
+    /// framebuffer keeps the dropped frame, the next flush that gets through shows it.
+    pub fn limit<F, R>(&mut self, now_us: u32, flush: F) -> Option<R>
+    where
+        F: FnOnce() -> R,
+    {
+        if self.ready(now_us) {
+            Some(flush())
+        } else {
+            self.dropped = self.dropped.wrapping_add(1);
+            None
+        }
+    }
+
+    /// Sleep until a frame may be sent instead of dropping it, `now_us` is the current time. The
+    /// next interval starts when the sleep ends.
+    pub fn wait<DELAY>(&mut self, delay: &mut DELAY, now_us: u32)
+    where
+        DELAY: DelayNs,
+    {
+        let remaining = self.last_frame_us.map_or(0, |last| {
+            self.min_interval_us
+                .saturating_sub(now_us.wrapping_sub(last))
+        });
+        if remaining > 0 {
+            delay.delay_us(remaining);
+        }
+        self.last_frame_us = Some(now_us.wrapping_add(remaining));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockDelay;
+
+    #[test]
+    fn limiter_drops_frames_inside_the_interval() {
+        let mut limiter = FrameLimiter::new(50);
+        assert_eq!(limiter.min_interval_us(), 20_000);
+
+        let mut sent = Vec::new();
+        for now_us in [0, 5_000, 19_999, 20_000, 30_000, 39_999] {
+            if limiter.limit(now_us, || now_us).is_some() {
+                sent.push(now_us);
+            }
+        }
+        // 30_000 is dropped, so the interval still runs from 20_000
+        assert_eq!(sent, [0, 20_000]);
+        assert_eq!(limiter.dropped(), 4);
+        assert!(limiter.ready(40_000));
+
+        // A wrapping timer still measures the interval
+        let mut limiter = FrameLimiter::new(50);
+        assert!(limiter.ready(u32::MAX - 4_999));
+        assert!(!limiter.ready(14_999));
+        assert!(limiter.ready(15_000));
+    }
+
+    #[test]
+    fn limiter_waits_for_the_rest_of_the_interval() {
+        let mut limiter = FrameLimiter::new(50);
+        let mut delay = MockDelay::default();
+        limiter.wait(&mut delay, 1_000);
+        assert_eq!(delay.total_ns, 0);
+
+        limiter.wait(&mut delay, 6_000);
+        assert_eq!(delay.total_ns, 15_000_000);
+        // The interval restarts when the wait ends, at 21_000
+        assert!(!limiter.ready(40_999));
+        assert!(limiter.ready(41_000));
+    }
+}
@@ -0,0 +1,151 @@
+//! Several framebuffers cycled on one display
+//!
+//! [`PagedDisplay`] keeps a fixed set of framebuffers, the pages, for one panel. Drawing goes to
+//! the active page, any page can be shown without touching the others, e.g. to render the frames
+//! of an animation up front and play them back at the speed of the bus. Every page costs a full
+//! framebuffer of RAM.
+
+use display_interface::WriteOnlyDataCommand;
+
+use crate::display::Display;
+use crate::error::Error;
+use crate::framebuffer::BYTES_PER_PIXEL;
+use crate::mode::displaymode::DisplayModeTrait;
+use crate::mode::GraphicsMode;
+
+/// A display drawing into one of `N` framebuffers at a time
+pub struct PagedDisplay<'a, DI, const N: usize>
+where
+    DI: WriteOnlyDataCommand,
+{
+    display: GraphicsMode<'a, DI>,
+    /// The pages, except for the active one which is lent to `display`
+    pages: [&'a mut [u8]; N],
+    active: usize,
+}
+
+impl<'a, DI, const N: usize> PagedDisplay<'a, DI, N>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Create a new PagedDisplay drawing into the first page. Panics if there are no pages or
+    /// one of them does not match the size of the display.
+    pub fn new(display: Display<DI>, mut pages: [&'a mut [u8]; N]) -> Self {
+        assert!(N > 0);
+        let frame_size = display.get_size().num_pixels() * BYTES_PER_PIXEL;
+        assert!(pages.iter().all(|page| page.len() == frame_size));
+        let first = core::mem::take(&mut pages[0]);
+        PagedDisplay {
+            display: GraphicsMode::new(display, first),
+            pages,
+            active: 0,
+        }
+    }
+
+    /// Release the display and the pages
+    pub fn release(mut self) -> (Display<DI>, [&'a mut [u8]; N]) {
+        let (display, buffer) = self.display.release();
+        self.pages[self.active] = buffer;
+        (display, self.pages)
+    }
+
+    /// Access the display drawing into the active page
+    pub fn display(&mut self) -> &mut GraphicsMode<'a, DI> {
+        &mut self.display
+    }
+
+    /// Get the index of the active page
+    pub fn page(&self) -> usize {
+        self.active
+    }
+
+    /// Get the number of pages
+    pub fn pages(&self) -> usize {
+        N
+    }
+
+    /// Make page `n` the one that is drawn to. Returns `Error::OutOfBounds` if there is no such
+    /// page.
+    pub fn select_page(&mut self, n: usize) -> Result<(), Error> {
+        if n >= N {
+            return Err(Error::OutOfBounds);
+        }
+        if n != self.active {
+            core::mem::swap(&mut self.display.buffer, &mut self.pages[self.active]);
+            core::mem::swap(&mut self.display.buffer, &mut self.pages[n]);
+            self.active = n;
+            // Nothing of the new page has been sent yet
+            self.display.mark_rows_dirty(0, u32::MAX);
+        }
+        Ok(())
+    }
+
+    /// Send page `n` to the display through
+    /// [`flush_continuous`](GraphicsMode::flush_continuous), leaving the active page as it is.
+    /// Returns `Error::OutOfBounds` if there is no such page. Showing the active page clears its
+    /// dirty rows, showing any other page marks all rows of the active one dirty.
+    pub fn show_page(&mut self, n: usize) -> Result<(), Error> {
+        if n >= N {
+            return Err(Error::OutOfBounds);
+        }
+        if n == self.active {
+            return self.display.flush_continuous();
+        }
+        core::mem::swap(&mut self.display.buffer, &mut self.pages[n]);
+        let flushed = self.display.flush_continuous();
+        core::mem::swap(&mut self.display.buffer, &mut self.pages[n]);
+        // The panel shows (part of) page `n` now, none of the active page is on it
+        self.display.mark_rows_dirty(0, u32::MAX);
+        flushed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framebuffer::{decode_pixel, encode_pixel};
+    use crate::mock;
+    use crate::properties::{DisplayRotation, DisplaySize};
+
+    const COLORS: [u16; 3] = [0xF800, 0x07E0, 0x001F];
+
+    fn frame(color: u16) -> Vec<u8> {
+        encode_pixel(color).repeat(128 * 128)
+    }
+
+    fn sent(color: u16) -> Vec<u8> {
+        decode_pixel(encode_pixel(color))
+            .to_be_bytes()
+            .repeat(128 * 128)
+    }
+
+    #[test]
+    fn show_page_cycles_pages_and_keeps_the_active_one() {
+        let (display, mock) = mock::display(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let pages = COLORS.map(|color| frame(color).leak());
+        let mut paged = PagedDisplay::new(display, pages);
+
+        for _ in 0..2 {
+            for (n, &color) in COLORS.iter().enumerate() {
+                paged.show_page(n).unwrap();
+                assert_eq!(mock::ram_data(&mock.take()), sent(color));
+                assert_eq!(paged.page(), 0);
+                assert_eq!(paged.display().fb(), frame(COLORS[0]));
+            }
+        }
+
+        // The last page shown was not the active one, all of it has to go out again
+        paged.display().flush_dirty_rows().unwrap();
+        assert_eq!(mock::ram_data(&mock.take()), sent(COLORS[0]));
+        paged.show_page(0).unwrap();
+        mock.take();
+        paged.display().flush_dirty_rows().unwrap();
+        assert!(mock::ram_data(&mock.take()).is_empty());
+        assert!(matches!(paged.show_page(3), Err(Error::OutOfBounds)));
+
+        let (_, pages) = paged.release();
+        for (page, &color) in pages.iter().zip(&COLORS) {
+            assert_eq!(**page, frame(color));
+        }
+    }
+}
@@ -1,5 +1,7 @@
 //! Display attributes
 
+use core::ops::BitOr;
+
 /// Display rotation.
 ///
 /// Note that 90º and 270º rotations are not supported by
@@ -16,6 +18,206 @@ pub enum DisplayRotation {
     Rotate270,
 }
 
+/// Mode of one of the two GPIO pins of the panel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GpioMode {
+    /// High impedance, input disabled
+    InputDisabled,
+    /// High impedance, input enabled
+    InputEnabled,
+    /// Driven low
+    OutputLow,
+    /// Driven high
+    OutputHigh,
+}
+
+impl GpioMode {
+    /// Two bit field of the pin in the `SetGpio` command
+    pub(crate) fn bits(self) -> u8 {
+        match self {
+            GpioMode::InputDisabled => 0b00,
+            GpioMode::InputEnabled => 0b01,
+            GpioMode::OutputLow => 0b10,
+            GpioMode::OutputHigh => 0b11,
+        }
+    }
+}
+
+/// Setting of the command lock (`FDh`), see
+/// [`Display::set_command_lock`](crate::display::Display::set_command_lock)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommandLockMode {
+    /// Accept commands again after `Lock`
+    Unlock,
+    /// Ignore every command but the command lock itself, as well as all data
+    Lock,
+    /// Make the commands `A2h`, `B1h`, `B3h`, `BBh`, `BEh` and `C1h` accessible, which they are
+    /// not after a reset
+    EnableProtected,
+    /// Make the commands `A2h`, `B1h`, `B3h`, `BBh`, `BEh` and `C1h` inaccessible again
+    DisableProtected,
+}
+
+impl CommandLockMode {
+    /// Data byte of the `CommandLock` command
+    pub(crate) fn bits(self) -> u8 {
+        match self {
+            CommandLockMode::Unlock => 0x12,
+            CommandLockMode::Lock => 0x16,
+            CommandLockMode::EnableProtected => 0xB1,
+            CommandLockMode::DisableProtected => 0xB0,
+        }
+    }
+}
+
+/// Colour depth of the display RAM, see
+/// [`Display::set_color_mode`](crate::display::Display::set_color_mode)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorMode {
+    /// 65k colours, two bytes of RGB565 per pixel
+    Rgb565,
+    /// 262k colours, three bytes of 6 bits each per pixel on 8-bit and serial buses
+    Rgb666,
+}
+
+impl ColorMode {
+    /// Colour depth field, bits 7:6 of the `SetRemap` command
+    pub(crate) fn bits(self) -> u8 {
+        match self {
+            // Rev 0.10 of the datasheet lists 00b as 256 colours, later revisions and the panels
+            // out there treat it as 65k like 01b
+            ColorMode::Rgb565 => 0b00,
+            ColorMode::Rgb666 => 0b10,
+        }
+    }
+}
+
+/// Coordinated contrast and pre-charge settings for a common goal, see
+/// [`Display::apply_profile`](crate::display::Display::apply_profile)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisplayProfile {
+    /// Bright and saturated: full master contrast, the highest pre-charge voltage of 0.60 x VCC
+    /// to lift the low gray levels and a second pre-charge of 8 DCLKs to charge the pixels fully
+    Vivid,
+    /// The settings of `init`: full master contrast, the reset pre-charge voltage of about
+    /// 0.50 x VCC and a second pre-charge of 1 DCLK
+    Natural,
+    /// Half the master contrast, which roughly halves the segment current and with it the power
+    /// drawn by the panel, with a lower pre-charge voltage of about 0.30 x VCC to match
+    PowerSaver,
+    /// Deep blacks: full master contrast, a pre-charge voltage of about 0.30 x VCC so that dark
+    /// pixels stay off and the longest second pre-charge of 15 DCLKs against crosstalk
+    HighContrast,
+}
+
+impl DisplayProfile {
+    /// Master contrast, pre-charge voltage level and second pre-charge period, as passed to
+    /// `set_master_contrast`, `set_precharge_voltage_level` and `set_second_precharge`
+    pub(crate) fn settings(self) -> (u8, u8, u8) {
+        match self {
+            DisplayProfile::Vivid => (0x0F, 0x1F, 0x08),
+            DisplayProfile::Natural => (0x0F, 0x17, 0x01),
+            DisplayProfile::PowerSaver => (0x07, 0x08, 0x01),
+            DisplayProfile::HighContrast => (0x0F, 0x08, 0x0F),
+        }
+    }
+}
+
+/// Patterns drawn by [`Display::draw_test_pattern`](crate::display::Display::draw_test_pattern)
+/// for focusing optics and checking a panel during bring-up
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestPattern {
+    /// Black and white squares with the given side length in pixels
+    Checkerboard(u8),
+    /// White one pixel lines every given number of pixels on black
+    Grid(u8),
+    /// Eight vertical bars of white, yellow, cyan, green, magenta, red, blue and black
+    ColorBars,
+    /// White lines through the centre of the display and a white border on black
+    Crosshair,
+}
+
+impl TestPattern {
+    /// RGB565 colour of the pixel at `x`, `y` on a `width` by `height` display
+    pub(crate) fn color(self, x: u8, y: u8, width: u8, height: u8) -> u16 {
+        const WHITE: u16 = 0xFFFF;
+        const BLACK: u16 = 0x0000;
+        let on = match self {
+            TestPattern::Checkerboard(size) => {
+                let size = size.max(1);
+                (x / size + y / size) % 2 == 0
+            }
+            TestPattern::Grid(spacing) => {
+                let spacing = spacing.max(1);
+                x % spacing == 0 || y % spacing == 0
+            }
+            TestPattern::ColorBars => {
+                const BARS: [u16; 8] = [
+                    0xFFFF, 0xFFE0, 0x07FF, 0x07E0, 0xF81F, 0xF800, 0x001F, 0x0000,
+                ];
+                return BARS[(x as usize * BARS.len() / width.max(1) as usize).min(BARS.len() - 1)];
+            }
+            TestPattern::Crosshair => {
+                x == width / 2
+                    || y == height / 2
+                    || x == 0
+                    || y == 0
+                    || x == width.saturating_sub(1)
+                    || y == height.saturating_sub(1)
+            }
+        };
+        if on {
+            WHITE
+        } else {
+            BLACK
+        }
+    }
+}
+
+/// Byte order of RGB565 pixel data handed to the driver
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// High byte first, as the display expects it
+    BigEndian,
+    /// Low byte first, e.g. `u16` framebuffers of little endian microcontrollers
+    LittleEndian,
+}
+
+impl ByteOrder {
+    /// Read a pixel stored in this byte order
+    pub fn read(self, bytes: [u8; 2]) -> u16 {
+        match self {
+            ByteOrder::BigEndian => u16::from_be_bytes(bytes),
+            ByteOrder::LittleEndian => u16::from_le_bytes(bytes),
+        }
+    }
+
+    /// Store a pixel in this byte order
+    pub fn write(self, color: u16) -> [u8; 2] {
+        match self {
+            ByteOrder::BigEndian => color.to_be_bytes(),
+            ByteOrder::LittleEndian => color.to_le_bytes(),
+        }
+    }
+}
+
+/// Byte order marker of embedded-graphics raw data, e.g. the `BO` parameter of a `Framebuffer`
+#[cfg(feature = "embedded-graphics")]
+pub trait RawByteOrder {
+    /// Byte order that the marker stands for
+    const ORDER: ByteOrder;
+}
+
+#[cfg(feature = "embedded-graphics")]
+impl RawByteOrder for embedded_graphics::pixelcolor::raw::BigEndian {
+    const ORDER: ByteOrder = ByteOrder::BigEndian;
+}
+
+#[cfg(feature = "embedded-graphics")]
+impl RawByteOrder for embedded_graphics::pixelcolor::raw::LittleEndian {
+    const ORDER: ByteOrder = ByteOrder::LittleEndian;
+}
+
 /// Display size enumeration
 #[derive(Clone, Copy)]
 pub enum DisplaySize {
@@ -35,9 +237,97 @@ impl DisplaySize {
         }
     }
 
+    /// Highest column address of the display RAM that is visible on the panel
+    pub fn max_column(&self) -> u8 {
+        self.dimensions().0 - 1
+    }
+
+    /// Highest row address of the display RAM that is visible on the panel
+    pub fn max_row(&self) -> u8 {
+        self.dimensions().1 - 1
+    }
+
+    /// Optional controller features available on panels of this size. Both sizes are driven by
+    /// the same SSD1351 and only differ in the MUX ratio, so they share the full command set of
+    /// the datasheet (Rev 0.10 in `docs/`, section 9): scrolling `96h`/`9Eh`/`9Fh`, the 262k
+    /// colour modes of `A0h` and the GPIO pins of `B5h`.
+    pub fn capabilities(&self) -> Capabilities {
+        match *self {
+            DisplaySize::Display128x128 | DisplaySize::Display128x96 => {
+                Capabilities::SCROLLING | Capabilities::COLOR_18BIT | Capabilities::GPIO
+            }
+        }
+    }
+
     /// Get total number of pixels
     pub(crate) fn num_pixels(&self) -> usize {
         let (w, h) = self.dimensions();
         w as usize * h as usize
     }
 }
+
+/// A rectangular area of the display in pixels, usable without the `graphics` feature
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    /// Column of the left edge
+    pub x: u8,
+    /// Row of the top edge
+    pub y: u8,
+    /// Width in pixels
+    pub width: u8,
+    /// Height in pixels
+    pub height: u8,
+}
+
+/// Set of optional controller features, combined like bitflags
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Capabilities(u8);
+
+impl Capabilities {
+    /// Horizontal scrolling, commands `96h`, `9Eh` and `9Fh`
+    pub const SCROLLING: Self = Capabilities(1 << 0);
+    /// 262k colour depth from 18-bit pixel data, `A0h` with `A[7:6]` set to `10b` or `11b`
+    pub const COLOR_18BIT: Self = Capabilities(1 << 1);
+    /// Two GPIO pins, command `B5h`
+    pub const GPIO: Self = Capabilities(1 << 2);
+
+    /// No capabilities at all
+    pub const fn empty() -> Self {
+        Capabilities(0)
+    }
+
+    /// Whether every capability of `other` is also in `self`
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Raw bits of the set
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+}
+
+impl BitOr for Capabilities {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Capabilities(self.0 | rhs.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn max_addresses_match_the_display_size() {
+        let sizes = [
+            (DisplaySize::Display128x128, 127, 127),
+            (DisplaySize::Display128x96, 127, 95),
+        ];
+        for (size, max_column, max_row) in sizes {
+            assert_eq!(size.max_column(), max_column);
+            assert_eq!(size.max_row(), max_row);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! One display shared between async tasks
+//!
+//! [`SharedDisplay`] puts a [`GraphicsMode`] behind an `embassy_sync` mutex, so that several tasks
+//! can draw to the same panel. The lock is held for one closure at a time: everything drawn inside
+//! it (and with the `buffered` feature, the flush that follows) goes out without another task
+//! cutting in, but nothing is held between two calls. Draw a whole widget per call rather than a
+//! pixel, a task taking the lock for every pixel spends more time waiting than drawing.
+//!
+//! The closures are synchronous. To `.await` while holding the lock, e.g. with the `async_draw`
+//! feature, use [`lock`](SharedDisplay::lock) and draw through the guard.
+//!
+//! `WriteOnlyDataCommand` has no way of reporting a busy bus, a transfer simply blocks until it
+//! is done. Busy is only detected here, at the lock: with the `buffered` feature
+//! [`try_flush`](SharedDisplay::try_flush) skips the flush instead of waiting while another task
+//! holds the display.
+
+use display_interface::WriteOnlyDataCommand;
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::mutex::{Mutex, MutexGuard};
+
+#[cfg(feature = "buffered")]
+use crate::error::Error;
+use crate::mode::GraphicsMode;
+
+/// A display that can be drawn to from several tasks
+pub struct SharedDisplay<'a, M, DI>
+where
+    M: RawMutex,
+    DI: WriteOnlyDataCommand,
+{
+    display: Mutex<M, GraphicsMode<'a, DI>>,
+}
+
+impl<'a, M, DI> SharedDisplay<'a, M, DI>
+where
+    M: RawMutex,
+    DI: WriteOnlyDataCommand,
+{
+    /// Create a new SharedDisplay from an initialised display
+    pub fn new(display: GraphicsMode<'a, DI>) -> Self {
+        SharedDisplay {
+            display: Mutex::new(display),
+        }
+    }
+
+    /// Release the display
+    pub fn release(self) -> GraphicsMode<'a, DI> {
+        self.display.into_inner()
+    }
+
+    /// Wait for the display and keep it locked until the guard is dropped
+    pub async fn lock(&self) -> MutexGuard<'_, M, GraphicsMode<'a, DI>> {
+        self.display.lock().await
+    }
+
+    /// Wait for the display and run `draw` on it with the lock held, returning its result
+    pub async fn draw<F, R>(&self, draw: F) -> R
+    where
+        F: FnOnce(&mut GraphicsMode<'a, DI>) -> R,
+    {
+        draw(&mut *self.display.lock().await)
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Wait for the display, run `draw` on it and flush the framebuffer through
+    /// [`flush_continuous`](GraphicsMode::flush_continuous) before releasing the lock, so that the
+    /// frame goes out with none of the other tasks' changes halfway in
+    pub async fn draw_and_flush<F, R>(&self, draw: F) -> Result<R, Error>
+    where
+        F: FnOnce(&mut GraphicsMode<'a, DI>) -> R,
+    {
+        let mut display = self.display.lock().await;
+        let result = draw(&mut display);
+        display.flush_continuous()?;
+        Ok(result)
+    }
+
+    #[cfg(feature = "buffered")]
+    /// Flush the framebuffer through [`flush_continuous`](GraphicsMode::flush_continuous) if the
+    /// display is free, returning `Ok(false)` right away if another task holds it. Nothing is
+    /// sent in that case and the changes stay pending for a later call.
+    pub fn try_flush(&self) -> Result<bool, Error> {
+        match self.display.try_lock() {
+            Ok(mut display) => display.flush_continuous().map(|()| true),
+            Err(_) => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{self, MockInterface};
+    use crate::properties::{DisplayRotation, DisplaySize};
+    use core::pin::pin;
+    use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+
+    fn shared() -> (
+        SharedDisplay<'static, NoopRawMutex, MockInterface>,
+        MockInterface,
+    ) {
+        let (display, mock) = mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        (SharedDisplay::new(display), mock)
+    }
+
+    #[cfg(feature = "buffered")]
+    #[test]
+    fn try_flush_leaves_a_locked_display_alone() {
+        let (shared, mock) = shared();
+        let mut display = mock::block_on(shared.lock());
+        display.set_pixel(3, 5, 0xF800);
+        assert!(matches!(shared.try_flush(), Ok(false)));
+        assert_eq!(mock.take(), []);
+
+        // Row 5 is still the only pending one
+        display.flush_dirty_rows().unwrap();
+        let transfers = mock.take();
+        assert_eq!(mock::args(&transfers, 0x75), [[5, 5]]);
+        drop(display);
+
+        assert!(matches!(shared.try_flush(), Ok(true)));
+        assert_eq!(mock::ram_data(&mock.take()).len(), 128 * 128 * 2);
+    }
+
+    #[test]
+    fn draws_of_two_tasks_both_reach_the_display() {
+        let (shared, mock) = shared();
+        let (first, second) = (&shared, &shared);
+
+        let mut display = mock::block_on(first.lock());
+        let mut waiting = pin!(second.draw(|display| display.set_pixels(&[(7, 9, 0x07E0)])));
+        assert!(mock::poll_once(waiting.as_mut()).is_pending());
+        display.set_pixels(&[(2, 4, 0xF800)]).unwrap();
+        #[cfg(not(feature = "buffered"))]
+        assert_eq!(mock::ram_data(&mock.take()), [0xF8, 0x00]);
+        drop(display);
+
+        let core::task::Poll::Ready(result) = mock::poll_once(waiting.as_mut()) else {
+            panic!("the second draw is still waiting for the lock");
+        };
+        result.unwrap();
+        #[cfg(not(feature = "buffered"))]
+        assert_eq!(mock::ram_data(&mock.take()), [0x07, 0xE0]);
+        #[cfg(feature = "buffered")]
+        {
+            let display = mock::block_on(shared.lock());
+            assert_eq!(mock::pixel(&display, 2, 4), 0xF800);
+            assert_eq!(mock::pixel(&display, 7, 9), 0x07E0);
+            assert_eq!(mock.take(), []);
+        }
+    }
+}
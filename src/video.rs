@@ -0,0 +1,143 @@
+//! Video in a part of the screen
+//!
+//! [`VideoRegion`] streams frames of raw RGB565 data into a fixed rectangle, e.g. a small video
+//! widget next to other content. Every frame waits for the next estimated frame boundary of a
+//! [`FramePacer`] and then goes out through one window. The draw window cache of the display turns
+//! that window into a lone `WriteRam` as long as nothing else was drawn in between, so the
+//! window is only programmed once for a run of frames.
+//!
+//! The frames bypass the framebuffer (if any), a later full `flush` draws over the region.
+
+use display_interface::WriteOnlyDataCommand;
+use hal::delay::DelayNs;
+
+use crate::error::Error;
+use crate::mode::GraphicsMode;
+use crate::pacer::FramePacer;
+use crate::properties::Region;
+
+/// A rectangle of the display showing paced frames of raw data
+pub struct VideoRegion {
+    area: Region,
+    pacer: FramePacer,
+    frames: u32,
+}
+
+impl VideoRegion {
+    /// Create a new VideoRegion showing frames in `area`, given in the current orientation, at the
+    /// pace of `pacer`
+    pub fn new(area: Region, pacer: FramePacer) -> Self {
+        VideoRegion {
+            area,
+            pacer,
+            frames: 0,
+        }
+    }
+
+    /// Get the area the frames are shown in
+    pub fn area(&self) -> Region {
+        self.area
+    }
+
+    /// Number of bytes of every frame, two per pixel of the area
+    pub fn frame_size_bytes(&self) -> usize {
+        self.area.width as usize * self.area.height as usize * 2
+    }
+
+    /// Get the number of frames presented so far
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// Wait for the next frame boundary and send `frame`, packed big endian RGB565 row by row.
+    /// `elapsed_ns` is the time spent since the previous call returned, see
+    /// [`FramePacer::wait`].
+    ///
+    /// Returns `Error::InvalidData` if `frame` is not exactly
+    /// [`frame_size_bytes`](Self::frame_size_bytes) long and `Error::OutOfBounds` if the area
+    /// does not fit on the display, both without waiting or sending anything.
+    pub fn present<DI, DELAY>(
+        &mut self,
+        display: &mut GraphicsMode<'_, DI>,
+        delay: &mut DELAY,
+        elapsed_ns: u32,
+        frame: &[u8],
+    ) -> Result<(), Error>
+    where
+        DI: WriteOnlyDataCommand,
+        DELAY: DelayNs,
+    {
+        if frame.len() != self.frame_size_bytes() {
+            return Err(Error::InvalidData);
+        }
+        let Region {
+            x,
+            y,
+            width,
+            height,
+        } = self.area;
+        let bounds = display.display_bounds();
+        if x as u16 + width as u16 > bounds.width as u16
+            || y as u16 + height as u16 > bounds.height as u16
+        {
+            return Err(Error::OutOfBounds);
+        }
+        if frame.is_empty() {
+            return Ok(());
+        }
+
+        self.pacer.wait(delay, elapsed_ns);
+        let mut stream = display.begin_stream((x, y), (x + width, y + height))?;
+        stream.push(frame)?;
+        stream.end_stream()?;
+        self.frames = self.frames.wrapping_add(1);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{self, MockDelay};
+    use crate::properties::{DisplayRotation, DisplaySize};
+
+    #[test]
+    fn frames_share_one_window() {
+        let (mut display, mock) =
+            mock::graphics(DisplaySize::Display128x128, DisplayRotation::Rotate0);
+        let area = Region {
+            x: 10,
+            y: 20,
+            width: 8,
+            height: 4,
+        };
+        let mut video = VideoRegion::new(area, FramePacer::new(1_000_000));
+        let mut delay = MockDelay::default();
+        assert_eq!(video.frame_size_bytes(), 64);
+
+        video
+            .present(&mut display, &mut delay, 0, &[0xF8; 64])
+            .unwrap();
+        let transfers = mock.take();
+        assert_eq!(mock::commands(&transfers), [0x15, 0x75, 0x5C]);
+        assert_eq!(mock::args(&transfers, 0x15), [[10, 17]]);
+        assert_eq!(mock::args(&transfers, 0x75), [[20, 23]]);
+        assert_eq!(mock::ram_data(&transfers), [0xF8; 64]);
+
+        video
+            .present(&mut display, &mut delay, 400_000, &[0x1F; 64])
+            .unwrap();
+        let transfers = mock.take();
+        assert_eq!(mock::commands(&transfers), [0x5C]);
+        assert_eq!(mock::ram_data(&transfers), [0x1F; 64]);
+        assert_eq!(video.frames(), 2);
+        assert_eq!(delay.total_ns, 1_600_000);
+
+        assert!(matches!(
+            video.present(&mut display, &mut delay, 0, &[0; 62]),
+            Err(Error::InvalidData)
+        ));
+        assert_eq!(mock.take(), []);
+        assert_eq!(delay.total_ns, 1_600_000);
+    }
+}